@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use settings::paths;
+use util::shell::Shell;
+
+/// Command-line options for launching a terminal window.
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    name = "alacrterm",
+    version,
+    about = "A GPU-accelerated terminal emulator"
+)]
+pub struct Args {
+    /// Command and arguments to run instead of the default shell. Must be the last option.
+    #[arg(
+        short = 'e',
+        long = "command",
+        value_name = "PROGRAM",
+        num_args = 1..,
+        allow_hyphen_values = true
+    )]
+    pub command: Vec<String>,
+
+    /// Directory the terminal starts in.
+    #[arg(long, value_name = "DIR")]
+    pub working_directory: Option<PathBuf>,
+
+    /// Title of the window and the first terminal tab.
+    #[arg(short = 'T', long)]
+    pub title: Option<String>,
+
+    /// Settings file to load instead of the default one.
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Keep terminals open after their process exits.
+    #[arg(long)]
+    pub hold: bool,
+
+    /// Window class (the app id on Wayland) used by window managers and desktop launchers.
+    #[arg(long, value_name = "NAME")]
+    pub class: Option<String>,
+
+    /// Print the JSON schema of the settings file and exit.
+    #[arg(long)]
+    pub print_settings_schema: bool,
+}
+
+impl Args {
+    /// The shell the first terminal runs: `--command` titled by `--title` if given, otherwise
+    /// `default`.
+    pub fn shell(&self, default: Shell) -> Shell {
+        match self.command.split_first() {
+            Some((program, args)) => Shell::WithArguments {
+                program: program.clone(),
+                args: args.to_vec(),
+                title_override: self.title.clone(),
+            },
+            None => default,
+        }
+    }
+
+    /// The title of the first terminal when it runs `default`, which has no title of its own.
+    pub fn default_shell_title(&self) -> Option<String> {
+        self.title.clone().filter(|_| self.command.is_empty())
+    }
+
+    /// The user settings file, `--config` or the one in the configuration directory.
+    pub fn settings_file(&self) -> Option<PathBuf> {
+        self.config.clone().or_else(paths::settings_file)
+    }
+
+    /// The directory the first terminal starts in, defaulting to the current one.
+    pub fn working_directory(&self) -> Option<PathBuf> {
+        self.working_directory
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_title_goes_with_command() {
+        let args = Args::parse_from(["alacrterm", "-T", "logs", "-e", "tail", "-f", "log"]);
+        assert_eq!(
+            args.shell(Shell::System),
+            Shell::WithArguments {
+                program: "tail".into(),
+                args: vec!["-f".into(), "log".into()],
+                title_override: Some("logs".into()),
+            }
+        );
+        assert_eq!(args.default_shell_title(), None);
+
+        let args = Args::parse_from(["alacrterm", "--title", "logs"]);
+        assert_eq!(args.shell(Shell::System), Shell::System);
+        assert_eq!(args.default_shell_title().as_deref(), Some("logs"));
+    }
+}
@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use gpui::{App, Global, KeyBinding};
+use settings::SettingsError;
+
+/// The key bindings registered by libraries before the keymaps were loaded, which have to be
+/// restored whenever the keymaps are reloaded.
+struct LibraryKeyBindings(Vec<KeyBinding>);
+
+impl Global for LibraryKeyBindings {}
+
+/// Remembers the key bindings registered so far. Call after initializing the libraries.
+pub fn init(cx: &mut App) {
+    let key_bindings = cx.key_bindings().borrow().bindings().cloned().collect();
+    cx.set_global(LibraryKeyBindings(key_bindings));
+}
+
+/// Replaces the key bindings with the default keymap followed by the user keymap at `path`, if
+/// any, so user bindings take precedence. Returns the errors of the user keymap.
+pub fn load_keymaps(path: Option<&Path>, cx: &mut App) -> Vec<SettingsError> {
+    let library_key_bindings = cx.global::<LibraryKeyBindings>().0.clone();
+    let default_key_bindings = settings::default_key_bindings(cx);
+    let (user_key_bindings, errors) = path
+        .map(|path| settings::user_key_bindings(path, cx))
+        .unwrap_or_default();
+
+    cx.clear_key_bindings();
+    cx.bind_keys(library_key_bindings);
+    cx.bind_keys(default_key_bindings);
+    cx.bind_keys(user_key_bindings);
+    errors
+}
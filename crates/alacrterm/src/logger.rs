@@ -0,0 +1,229 @@
+//! A logger writing to stderr and a log file, with per-module levels from the `log` setting.
+
+use std::{
+    fs::{self, File},
+    io::Write as _,
+    path::PathBuf,
+    str::FromStr as _,
+    sync::{Mutex, RwLock},
+};
+
+use gpui::App;
+use log::{LevelFilter, Log, Metadata, Record};
+use settings::{SettingsStore, paths};
+
+/// The level of modules no `log` setting applies to.
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+/// The size past which the log file is rotated, keeping the previous one next to it.
+const MAX_LOG_FILE_SIZE: u64 = 1024 * 1024;
+
+static LOGGER: Logger = Logger {
+    scopes: RwLock::new(Vec::new()),
+    file: Mutex::new(None),
+};
+
+struct Logger {
+    /// The level of each configured scope, a module path such as `terminal` or
+    /// `terminal::pty_info` that also applies to the modules inside it.
+    scopes: RwLock<Vec<(String, LevelFilter)>>,
+    file: Mutex<Option<LogFile>>,
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = File::options().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        let mut log_file = Self { path, file, size };
+        if log_file.size > MAX_LOG_FILE_SIZE {
+            log_file.rotate()?;
+        }
+        Ok(log_file)
+    }
+
+    fn write(&mut self, line: &str) -> std::io::Result<()> {
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        if self.size > MAX_LOG_FILE_SIZE {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    /// Moves the log file to `alacrterm.log.old`, replacing the previous one, and starts anew.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        fs::rename(&self.path, self.path.with_extension("log.old"))?;
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Logger {
+    /// Returns the level of the most specific scope `target` is in.
+    fn level(&self, target: &str) -> LevelFilter {
+        let scopes = self.scopes.read().unwrap_or_else(|err| err.into_inner());
+        scopes
+            .iter()
+            .filter(|(scope, _)| {
+                target
+                    .strip_prefix(scope.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(scope, _)| scope.len())
+            .map_or(DEFAULT_LEVEL, |(_, level)| *level)
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} {:<5} [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        eprint!("{line}");
+        let mut file = self.file.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(log_file) = file.as_mut()
+            && let Err(err) = log_file.write(&line)
+        {
+            eprintln!("failed to write to {:?}: {err}", log_file.path);
+            // Stop trying rather than reporting the failure for every message.
+            *file = None;
+        }
+    }
+
+    fn flush(&self) {
+        let mut file = self.file.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(log_file) = file.as_mut() {
+            log_file.file.flush().ok();
+        }
+    }
+}
+
+/// Installs the logger, writing to the log file in the state directory. Until the settings are
+/// loaded, modules log at the default level.
+pub fn init() {
+    let path = paths::log_file();
+    if let Some(path) = &path {
+        match LogFile::open(path.clone()) {
+            Ok(log_file) => {
+                *LOGGER.file.lock().unwrap_or_else(|err| err.into_inner()) = Some(log_file)
+            }
+            Err(err) => eprintln!("failed to open log file {path:?}: {err}"),
+        }
+    }
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(DEFAULT_LEVEL);
+    }
+    if path.is_none() {
+        log::warn!("there is no state directory, only logging to stderr");
+    }
+}
+
+/// Applies the `log` setting, now and whenever the settings are reloaded.
+pub fn observe_settings(cx: &mut App) {
+    apply_settings(cx);
+    cx.observe_global::<SettingsStore>(apply_settings).detach();
+}
+
+fn apply_settings(cx: &mut App) {
+    let settings = cx
+        .global::<SettingsStore>()
+        .global_settings()
+        .log
+        .clone()
+        .unwrap_or_default();
+    let mut scopes = Vec::with_capacity(settings.len());
+    for (scope, level) in settings {
+        match LevelFilter::from_str(&level) {
+            Ok(level) => scopes.push((scope, level)),
+            Err(_) => log::warn!("invalid log level {level:?} for {scope:?}"),
+        }
+    }
+
+    let max_level = scopes
+        .iter()
+        .map(|(_, level)| *level)
+        .fold(DEFAULT_LEVEL, Ord::max);
+    *LOGGER.scopes.write().unwrap_or_else(|err| err.into_inner()) = scopes;
+    log::set_max_level(max_level);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_specific_scope_wins() {
+        let logger = Logger {
+            scopes: RwLock::new(vec![
+                ("terminal".to_string(), LevelFilter::Debug),
+                ("terminal::pty_info".to_string(), LevelFilter::Off),
+                ("gpui".to_string(), LevelFilter::Warn),
+            ]),
+            file: Mutex::new(None),
+        };
+
+        assert_eq!(logger.level("terminal"), LevelFilter::Debug);
+        assert_eq!(logger.level("terminal::alacritty"), LevelFilter::Debug);
+        assert_eq!(logger.level("terminal::pty_info"), LevelFilter::Off);
+        assert_eq!(logger.level("terminal::pty_info::inner"), LevelFilter::Off);
+        // A scope only covers the modules inside it, not the ones sharing its prefix.
+        assert_eq!(logger.level("terminal_view"), DEFAULT_LEVEL);
+        assert_eq!(logger.level("gpui_component"), DEFAULT_LEVEL);
+        assert_eq!(logger.level("gpui::window"), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn test_log_file_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alacrterm.log");
+        let old_path = dir.path().join("alacrterm.log.old");
+        let line = format!("{}\n", "x".repeat(1023));
+
+        let mut log_file = LogFile::open(path.clone()).unwrap();
+        for _ in 0..MAX_LOG_FILE_SIZE / line.len() as u64 {
+            log_file.write(&line).unwrap();
+        }
+        assert!(!old_path.exists());
+
+        log_file.write("past the limit\n").unwrap();
+        assert_eq!(
+            fs::metadata(&old_path).unwrap().len(),
+            MAX_LOG_FILE_SIZE + 15
+        );
+        assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+
+        log_file.write("after rotation\n").unwrap();
+        drop(log_file);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "after rotation\n");
+
+        // A log file that grew too large before is rotated when opened.
+        fs::write(&path, "x".repeat(MAX_LOG_FILE_SIZE as usize + 1)).unwrap();
+        let log_file = LogFile::open(path.clone()).unwrap();
+        assert_eq!(log_file.size, 0);
+        assert_eq!(
+            fs::metadata(&old_path).unwrap().len(),
+            MAX_LOG_FILE_SIZE + 1
+        );
+    }
+}
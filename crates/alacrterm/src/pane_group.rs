@@ -0,0 +1,648 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gpui::{
+    AnyElement, Axis, Context, DragMoveEvent, Entity, Hsla, IntoElement, Render, Window, div,
+    prelude::*, px,
+};
+use gpui_component::{h_flex, v_flex};
+use terminal_view::TerminalView;
+
+const DIVIDER_SIZE: f32 = 4.0;
+/// The smallest share of its axis a pane can be resized down to.
+const MIN_PANE_FRACTION: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl SplitDirection {
+    pub fn axis(self) -> Axis {
+        match self {
+            SplitDirection::Up | SplitDirection::Down => Axis::Vertical,
+            SplitDirection::Left | SplitDirection::Right => Axis::Horizontal,
+        }
+    }
+
+    /// Whether the new member goes after the existing one along the axis.
+    fn increasing(self) -> bool {
+        matches!(self, SplitDirection::Down | SplitDirection::Right)
+    }
+}
+
+/// The layout of the panes inside a tab: a tree whose leaves are terminals and whose inner nodes
+/// split their space horizontally or vertically between their children.
+pub struct PaneGroup<P = Entity<TerminalView>> {
+    root: Member<P>,
+}
+
+enum Member<P> {
+    Pane(P),
+    Axis(PaneAxis<P>),
+}
+
+struct PaneAxis<P> {
+    axis: Axis,
+    members: Vec<Member<P>>,
+    /// Relative sizes of `members`, shared with the dividers so they can be dragged.
+    flexes: Rc<RefCell<Vec<f32>>>,
+}
+
+/// A pane's position inside the group, in fractions of the group's size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PaneRect {
+    left: f32,
+    top: f32,
+    right: f32,
+    bottom: f32,
+}
+
+impl<P: Clone + PartialEq> PaneGroup<P> {
+    pub fn new(pane: P) -> Self {
+        Self {
+            root: Member::Pane(pane),
+        }
+    }
+
+    pub fn contains(&self, pane: &P) -> bool {
+        self.root.contains(pane)
+    }
+
+    /// All panes in layout order.
+    pub fn panes(&self) -> Vec<&P> {
+        let mut panes = Vec::new();
+        self.root.collect_panes(&mut panes);
+        panes
+    }
+
+    /// Places `new_pane` next to `old_pane`. Returns false if `old_pane` is not in the group.
+    pub fn split(&mut self, old_pane: &P, new_pane: P, direction: SplitDirection) -> bool {
+        self.root.split(old_pane, new_pane, direction).is_ok()
+    }
+
+    /// Removes `pane`, collapsing any axis that is left with a single member. Returns false if
+    /// the pane is not in the group or is the last one in it.
+    pub fn remove(&mut self, pane: &P) -> bool {
+        match &mut self.root {
+            Member::Pane(_) => false,
+            Member::Axis(axis) => {
+                if !axis.remove(pane) {
+                    return false;
+                }
+                if axis.members.len() == 1 {
+                    self.root = axis.members.pop().unwrap();
+                }
+                true
+            }
+        }
+    }
+
+    /// Moves the divider nearest to `pane` along `axis` by `amount`, a fraction of the size of the
+    /// axis containing it. Positive amounts move the divider right or down.
+    pub fn resize(&mut self, pane: &P, axis: Axis, amount: f32) -> bool {
+        self.root.resize(pane, axis, amount)
+    }
+
+    /// Gives every pane in the group an equal share of its axis.
+    pub fn reset_sizes(&mut self) {
+        self.root.reset_sizes();
+    }
+
+    /// Finds the pane adjacent to `pane` in the given direction, preferring the one that
+    /// overlaps it the most.
+    pub fn find_pane_in_direction(&self, pane: &P, direction: SplitDirection) -> Option<&P> {
+        const EPSILON: f32 = 1e-4;
+
+        let mut rects = Vec::new();
+        self.root.collect_rects(
+            PaneRect {
+                left: 0.,
+                top: 0.,
+                right: 1.,
+                bottom: 1.,
+            },
+            &mut rects,
+        );
+        let (_, active) = *rects.iter().find(|(candidate, _)| *candidate == pane)?;
+
+        rects
+            .iter()
+            .filter(|(candidate, _)| *candidate != pane)
+            .filter_map(|(candidate, rect)| {
+                let (distance, overlap) = match direction {
+                    SplitDirection::Left => (
+                        active.left - rect.right,
+                        rect.bottom.min(active.bottom) - rect.top.max(active.top),
+                    ),
+                    SplitDirection::Right => (
+                        rect.left - active.right,
+                        rect.bottom.min(active.bottom) - rect.top.max(active.top),
+                    ),
+                    SplitDirection::Up => (
+                        active.top - rect.bottom,
+                        rect.right.min(active.right) - rect.left.max(active.left),
+                    ),
+                    SplitDirection::Down => (
+                        rect.top - active.bottom,
+                        rect.right.min(active.right) - rect.left.max(active.left),
+                    ),
+                };
+                (distance > -EPSILON && overlap > EPSILON)
+                    .then_some((*candidate, distance, overlap))
+            })
+            .min_by(|(_, distance_a, overlap_a), (_, distance_b, overlap_b)| {
+                distance_a
+                    .total_cmp(distance_b)
+                    .then_with(|| overlap_b.total_cmp(overlap_a))
+            })
+            .map(|(candidate, _, _)| candidate)
+    }
+}
+
+impl PaneGroup {
+    pub fn render(&self, active_pane: &Entity<TerminalView>, colors: PaneColors) -> AnyElement {
+        let highlight_active = matches!(self.root, Member::Axis(_));
+        self.root.render(active_pane, highlight_active, colors)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct PaneColors {
+    pub divider: Hsla,
+    pub divider_hover: Hsla,
+    pub active_border: Hsla,
+}
+
+impl<P: Clone + PartialEq> Member<P> {
+    fn contains(&self, pane: &P) -> bool {
+        match self {
+            Member::Pane(member) => member == pane,
+            Member::Axis(axis) => axis.members.iter().any(|member| member.contains(pane)),
+        }
+    }
+
+    fn collect_panes<'a>(&'a self, panes: &mut Vec<&'a P>) {
+        match self {
+            Member::Pane(pane) => panes.push(pane),
+            Member::Axis(axis) => {
+                for member in &axis.members {
+                    member.collect_panes(panes);
+                }
+            }
+        }
+    }
+
+    fn collect_rects<'a>(&'a self, rect: PaneRect, rects: &mut Vec<(&'a P, PaneRect)>) {
+        match self {
+            Member::Pane(pane) => rects.push((pane, rect)),
+            Member::Axis(axis) => {
+                let flexes = axis.flexes.borrow();
+                let total: f32 = flexes.iter().sum();
+                let mut offset = 0.;
+                for (member, flex) in axis.members.iter().zip(flexes.iter()) {
+                    let start = offset / total;
+                    offset += flex;
+                    let end = offset / total;
+                    let member_rect = match axis.axis {
+                        Axis::Horizontal => {
+                            let width = rect.right - rect.left;
+                            PaneRect {
+                                left: rect.left + width * start,
+                                right: rect.left + width * end,
+                                ..rect
+                            }
+                        }
+                        Axis::Vertical => {
+                            let height = rect.bottom - rect.top;
+                            PaneRect {
+                                top: rect.top + height * start,
+                                bottom: rect.top + height * end,
+                                ..rect
+                            }
+                        }
+                    };
+                    member.collect_rects(member_rect, rects);
+                }
+            }
+        }
+    }
+
+    /// Returns the new pane back if `old_pane` was not found.
+    fn split(&mut self, old_pane: &P, new_pane: P, direction: SplitDirection) -> Result<(), P> {
+        match self {
+            Member::Pane(pane) => {
+                if pane != old_pane {
+                    return Err(new_pane);
+                }
+                let old_member = Member::Pane(pane.clone());
+                let new_member = Member::Pane(new_pane);
+                let members = if direction.increasing() {
+                    vec![old_member, new_member]
+                } else {
+                    vec![new_member, old_member]
+                };
+                *self = Member::Axis(PaneAxis::new(direction.axis(), members));
+                Ok(())
+            }
+            Member::Axis(axis) => axis.split(old_pane, new_pane, direction),
+        }
+    }
+
+    fn resize(&mut self, pane: &P, axis: Axis, amount: f32) -> bool {
+        match self {
+            Member::Pane(_) => false,
+            Member::Axis(pane_axis) => pane_axis.resize(pane, axis, amount),
+        }
+    }
+
+    fn reset_sizes(&mut self) {
+        if let Member::Axis(axis) = self {
+            axis.flexes.borrow_mut().fill(1.);
+            for member in &mut axis.members {
+                member.reset_sizes();
+            }
+        }
+    }
+}
+
+impl Member<Entity<TerminalView>> {
+    fn render(
+        &self,
+        active_pane: &Entity<TerminalView>,
+        highlight_active: bool,
+        colors: PaneColors,
+    ) -> AnyElement {
+        match self {
+            Member::Pane(pane) => div()
+                .size_full()
+                .min_w_0()
+                .min_h_0()
+                .overflow_hidden()
+                .when(highlight_active, |div| {
+                    div.border_1().border_color(if pane == active_pane {
+                        colors.active_border
+                    } else {
+                        gpui::transparent_black()
+                    })
+                })
+                .child(pane.clone())
+                .into_any_element(),
+            Member::Axis(axis) => axis.render(active_pane, highlight_active, colors),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct DraggedDivider {
+    flexes: Rc<RefCell<Vec<f32>>>,
+    ix: usize,
+}
+
+impl Render for DraggedDivider {
+    fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+        gpui::Empty
+    }
+}
+
+impl<P: Clone + PartialEq> PaneAxis<P> {
+    fn new(axis: Axis, members: Vec<Member<P>>) -> Self {
+        let flexes = Rc::new(RefCell::new(vec![1.; members.len()]));
+        Self {
+            axis,
+            members,
+            flexes,
+        }
+    }
+
+    fn split(&mut self, old_pane: &P, mut new_pane: P, direction: SplitDirection) -> Result<(), P> {
+        for ix in 0..self.members.len() {
+            match &mut self.members[ix] {
+                Member::Pane(pane) if pane == old_pane && self.axis == direction.axis() => {
+                    // Split the existing pane's share in half so the other members keep their size.
+                    let mut flexes = self.flexes.borrow_mut();
+                    flexes[ix] /= 2.;
+                    let flex = flexes[ix];
+                    let new_ix = if direction.increasing() { ix + 1 } else { ix };
+                    flexes.insert(new_ix, flex);
+                    self.members.insert(new_ix, Member::Pane(new_pane));
+                    return Ok(());
+                }
+                member => match member.split(old_pane, new_pane, direction) {
+                    Ok(()) => return Ok(()),
+                    Err(pane) => new_pane = pane,
+                },
+            }
+        }
+        Err(new_pane)
+    }
+
+    fn remove(&mut self, pane: &P) -> bool {
+        let Some(ix) = self.members.iter().position(|member| member.contains(pane)) else {
+            return false;
+        };
+
+        match &mut self.members[ix] {
+            Member::Pane(_) => {
+                self.members.remove(ix);
+                let mut flexes = self.flexes.borrow_mut();
+                let flex = flexes.remove(ix);
+                // Hand the freed space to the neighbour that shared the divider.
+                let neighbour = ix.saturating_sub(1).min(flexes.len().saturating_sub(1));
+                if let Some(neighbour_flex) = flexes.get_mut(neighbour) {
+                    *neighbour_flex += flex;
+                }
+            }
+            Member::Axis(axis) => {
+                axis.remove(pane);
+                if axis.members.len() == 1 {
+                    self.members[ix] = axis.members.pop().unwrap();
+                }
+            }
+        }
+        true
+    }
+
+    fn resize(&mut self, pane: &P, axis: Axis, amount: f32) -> bool {
+        let Some(ix) = self.members.iter().position(|member| member.contains(pane)) else {
+            return false;
+        };
+
+        // Prefer the innermost axis so the divider closest to the pane moves.
+        if self.members[ix].resize(pane, axis, amount) {
+            return true;
+        }
+        if self.axis != axis || self.members.len() < 2 {
+            return false;
+        }
+
+        let divider_ix = if ix + 1 < self.members.len() {
+            ix
+        } else {
+            ix - 1
+        };
+        let mut flexes = self.flexes.borrow_mut();
+        let total: f32 = flexes.iter().sum();
+        let start: f32 = flexes[..divider_ix].iter().sum::<f32>() / total;
+        let position = (flexes[..=divider_ix].iter().sum::<f32>() / total) + amount;
+        move_divider(&mut flexes, divider_ix, start, position);
+        true
+    }
+}
+
+impl PaneAxis<Entity<TerminalView>> {
+    fn render(
+        &self,
+        active_pane: &Entity<TerminalView>,
+        highlight_active: bool,
+        colors: PaneColors,
+    ) -> AnyElement {
+        let flexes = self.flexes.borrow().clone();
+        let total: f32 = flexes.iter().sum();
+        let axis = self.axis;
+
+        let container = match axis {
+            Axis::Horizontal => h_flex(),
+            Axis::Vertical => v_flex(),
+        };
+
+        container
+            .size_full()
+            .min_w_0()
+            .min_h_0()
+            .overflow_hidden()
+            .on_drag_move({
+                let flexes = self.flexes.clone();
+                move |event: &DragMoveEvent<DraggedDivider>, window, cx| {
+                    let drag = event.drag(cx);
+                    if !Rc::ptr_eq(&drag.flexes, &flexes) {
+                        return;
+                    }
+                    let ix = drag.ix;
+                    let bounds = event.bounds;
+                    let position = match axis {
+                        Axis::Horizontal => {
+                            (event.event.position.x - bounds.origin.x) / bounds.size.width
+                        }
+                        Axis::Vertical => {
+                            (event.event.position.y - bounds.origin.y) / bounds.size.height
+                        }
+                    };
+
+                    let mut flexes = flexes.borrow_mut();
+                    let total: f32 = flexes.iter().sum();
+                    let start = flexes[..ix].iter().sum::<f32>() / total;
+                    move_divider(&mut flexes, ix, start, position);
+                    window.refresh();
+                }
+            })
+            .children(
+                self.members
+                    .iter()
+                    .zip(flexes.iter())
+                    .enumerate()
+                    .map(|(ix, (member, flex))| {
+                        let child = div()
+                            .flex()
+                            .flex_shrink()
+                            .flex_basis(gpui::relative(flex / total))
+                            .min_w_0()
+                            .min_h_0()
+                            .overflow_hidden()
+                            .map(|div| match axis {
+                                Axis::Horizontal => div.h_full(),
+                                Axis::Vertical => div.w_full(),
+                            })
+                            .child(member.render(active_pane, highlight_active, colors));
+
+                        if ix + 1 == self.members.len() {
+                            return vec![child.into_any_element()];
+                        }
+
+                        let divider = div()
+                            .id(("pane-divider", ix))
+                            .flex_none()
+                            .bg(colors.divider)
+                            .hover(|style| style.bg(colors.divider_hover))
+                            .map(|div| match axis {
+                                Axis::Horizontal => {
+                                    div.w(px(DIVIDER_SIZE)).h_full().cursor_col_resize()
+                                }
+                                Axis::Vertical => {
+                                    div.h(px(DIVIDER_SIZE)).w_full().cursor_row_resize()
+                                }
+                            })
+                            .on_drag(
+                                DraggedDivider {
+                                    flexes: self.flexes.clone(),
+                                    ix,
+                                },
+                                |divider, _, _, cx| {
+                                    cx.stop_propagation();
+                                    cx.new(|_| divider.clone())
+                                },
+                            );
+
+                        vec![child.into_any_element(), divider.into_any_element()]
+                    })
+                    .flatten()
+                    .collect::<Vec<_>>(),
+            )
+            .into_any_element()
+    }
+}
+
+/// Moves the divider after member `ix` to `position` (a fraction of the axis), taking the space
+/// from or giving it to member `ix + 1`. `start` is where member `ix` begins.
+fn move_divider(flexes: &mut [f32], ix: usize, start: f32, position: f32) {
+    let total: f32 = flexes.iter().sum();
+    let end = start + (flexes[ix] + flexes[ix + 1]) / total;
+    // Two members that already share less than twice the minimum have no room to move within.
+    let room = end - start;
+    if !position.is_finite() || !room.is_finite() || room < 2. * MIN_PANE_FRACTION {
+        return;
+    }
+    let position = position.clamp(start + MIN_PANE_FRACTION, end - MIN_PANE_FRACTION);
+    flexes[ix] = (position - start) * total;
+    flexes[ix + 1] = (end - position) * total;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL: PaneRect = PaneRect {
+        left: 0.,
+        top: 0.,
+        right: 1.,
+        bottom: 1.,
+    };
+
+    fn rect_of(group: &PaneGroup<usize>, pane: usize) -> PaneRect {
+        let mut rects = Vec::new();
+        group.root.collect_rects(FULL, &mut rects);
+        rects
+            .into_iter()
+            .find(|(candidate, _)| **candidate == pane)
+            .map(|(_, rect)| rect)
+            .unwrap()
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_move_divider() {
+        let mut flexes = vec![1., 1.];
+        move_divider(&mut flexes, 0, 0., 0.25);
+        assert_close(flexes[0], 0.5);
+        assert_close(flexes[1], 1.5);
+
+        // The divider stops short of squeezing either member below the minimum.
+        move_divider(&mut flexes, 0, 0., 0.);
+        assert_close(flexes[0], MIN_PANE_FRACTION * 2.);
+        move_divider(&mut flexes, 0, 0., 1.);
+        assert_close(flexes[1], MIN_PANE_FRACTION * 2.);
+
+        move_divider(&mut flexes, 0, 0., f32::NAN);
+        assert_close(flexes[1], MIN_PANE_FRACTION * 2.);
+    }
+
+    #[test]
+    fn test_move_divider_between_narrow_members() {
+        let mut flexes = vec![0.04, 0.04, 0.92];
+        move_divider(&mut flexes, 0, 0., 0.5);
+        assert_eq!(flexes, vec![0.04, 0.04, 0.92]);
+    }
+
+    #[test]
+    fn test_split_and_remove() {
+        let mut group = PaneGroup::new(0);
+        assert!(group.split(&0, 1, SplitDirection::Right));
+        assert!(group.split(&1, 2, SplitDirection::Down));
+        assert!(group.split(&0, 3, SplitDirection::Left));
+        assert!(!group.split(&4, 5, SplitDirection::Right));
+        assert_eq!(group.panes(), vec![&3, &0, &1, &2]);
+
+        // Splitting along the same axis halves the pane instead of nesting another axis.
+        assert_close(rect_of(&group, 3).right, 0.25);
+        assert_close(rect_of(&group, 0).right, 0.5);
+        assert_close(rect_of(&group, 1).bottom, 0.5);
+
+        assert!(group.remove(&1));
+        assert!(!group.contains(&1));
+        assert_eq!(group.panes(), vec![&3, &0, &2]);
+        assert_close(rect_of(&group, 2).top, 0.);
+
+        assert!(group.remove(&3));
+        assert!(group.remove(&0));
+        assert!(matches!(group.root, Member::Pane(2)));
+        assert!(!group.remove(&2));
+    }
+
+    #[test]
+    fn test_find_pane_in_direction() {
+        // 0 | 1
+        //   | -
+        //   | 2
+        let mut group = PaneGroup::new(0);
+        group.split(&0, 1, SplitDirection::Right);
+        group.split(&1, 2, SplitDirection::Down);
+
+        assert_eq!(
+            group.find_pane_in_direction(&0, SplitDirection::Right),
+            Some(&1)
+        );
+        assert_eq!(
+            group.find_pane_in_direction(&2, SplitDirection::Left),
+            Some(&0)
+        );
+        assert_eq!(
+            group.find_pane_in_direction(&2, SplitDirection::Up),
+            Some(&1)
+        );
+        assert_eq!(
+            group.find_pane_in_direction(&1, SplitDirection::Down),
+            Some(&2)
+        );
+        assert_eq!(group.find_pane_in_direction(&0, SplitDirection::Left), None);
+        assert_eq!(group.find_pane_in_direction(&3, SplitDirection::Left), None);
+
+        // The pane overlapping the most wins.
+        group.resize(&1, Axis::Vertical, 0.2);
+        assert_eq!(
+            group.find_pane_in_direction(&0, SplitDirection::Right),
+            Some(&1)
+        );
+        group.resize(&1, Axis::Vertical, -0.4);
+        assert_eq!(
+            group.find_pane_in_direction(&0, SplitDirection::Right),
+            Some(&2)
+        );
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut group = PaneGroup::new(0);
+        group.split(&0, 1, SplitDirection::Right);
+        group.split(&1, 2, SplitDirection::Down);
+
+        // The innermost axis along the requested direction moves.
+        assert!(group.resize(&2, Axis::Vertical, -0.25));
+        assert_close(rect_of(&group, 2).top, 0.25);
+        assert!(group.resize(&2, Axis::Horizontal, 0.25));
+        assert_close(rect_of(&group, 2).left, 0.75);
+        assert!(!group.resize(&0, Axis::Vertical, 0.1));
+        assert!(!group.resize(&3, Axis::Horizontal, 0.1));
+
+        group.reset_sizes();
+        assert_close(rect_of(&group, 2).top, 0.5);
+        assert_close(rect_of(&group, 2).left, 0.5);
+    }
+}
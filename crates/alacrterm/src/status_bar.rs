@@ -0,0 +1,103 @@
+use gpui::{App, Entity, IntoElement, div, prelude::*};
+use gpui_component::{ActiveTheme, h_flex};
+use settings::{Settings, SettingsContent};
+use terminal::Modes;
+use terminal_view::TerminalView;
+use util::paths::PathExt as _;
+
+/// Which items the status bar shows.
+pub struct StatusBarSettings {
+    pub show: bool,
+    pub working_directory: bool,
+    pub process: bool,
+    pub terminal_size: bool,
+    pub modes: bool,
+    pub scroll_position: bool,
+}
+
+impl Settings for StatusBarSettings {
+    fn from_settings(content: &SettingsContent) -> Self {
+        // Like in `default.json`, everything is shown unless turned off.
+        let content = content.status_bar.clone().unwrap_or_default();
+        Self {
+            show: content.show.unwrap_or(true),
+            working_directory: content.show_working_directory.unwrap_or(true),
+            process: content.show_process.unwrap_or(true),
+            terminal_size: content.show_terminal_size.unwrap_or(true),
+            modes: content.show_modes.unwrap_or(true),
+            scroll_position: content.show_scroll_position.unwrap_or(true),
+        }
+    }
+}
+
+/// Renders the status bar describing `terminal_view`, unless it is turned off.
+pub fn render(terminal_view: &Entity<TerminalView>, cx: &App) -> Option<impl IntoElement> {
+    let settings = StatusBarSettings::get_global(cx);
+    if !settings.show {
+        return None;
+    }
+
+    let terminal = terminal_view.read(cx).terminal().read(cx);
+    let content = terminal.last_content();
+    let mut location = Vec::new();
+    let mut state = Vec::new();
+    if settings.working_directory
+        && let Some(working_directory) = terminal.working_directory()
+    {
+        // Home directories differ between hosts, so only compact local paths.
+        location.push(match terminal.working_directory_host() {
+            Some(host) => format!("{host}:{}", working_directory.to_string_lossy()),
+            None => working_directory.compact().to_string_lossy().into_owned(),
+        });
+    }
+    if settings.process
+        && let Some(process) = terminal.foreground_process_command_name()
+    {
+        location.push(process);
+    }
+    if settings.modes {
+        if terminal.vi_mode_enabled() {
+            state.push("VI".to_string());
+        }
+        if content.mode.contains(Modes::ALT_SCREEN) {
+            state.push("ALT".to_string());
+        }
+    }
+    if settings.scroll_position && content.display_offset > 0 {
+        let history_size = terminal.total_lines() - terminal.viewport_lines();
+        state.push(format!("↑ {}/{history_size}", content.display_offset));
+    }
+    if settings.terminal_size {
+        let bounds = &content.terminal_bounds;
+        state.push(format!("{}×{}", bounds.num_columns(), bounds.num_lines()));
+    }
+
+    let colors = &cx.theme().colors;
+    Some(
+        h_flex()
+            .w_full()
+            .px_2()
+            .py_0p5()
+            .gap_4()
+            .justify_between()
+            .border_t_1()
+            .border_color(colors.border)
+            .bg(colors.title_bar)
+            .text_xs()
+            .text_color(colors.muted_foreground)
+            .child(
+                h_flex()
+                    .min_w_0()
+                    .gap_4()
+                    .overflow_hidden()
+                    .whitespace_nowrap()
+                    .children(location.into_iter().map(|item| div().child(item))),
+            )
+            .child(
+                h_flex()
+                    .flex_none()
+                    .gap_4()
+                    .children(state.into_iter().map(|item| div().child(item))),
+            ),
+    )
+}
@@ -0,0 +1,853 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Result;
+use gpui::{
+    Action, App, AppContext as _, Axis, Context, Entity, EntityId, Focusable, IntoElement,
+    MouseButton, Render, SharedString, Subscription, Task, WeakEntity, Window, actions, div,
+    prelude::*,
+};
+use gpui_component::{ActiveTheme, TitleBar, h_flex, v_flex};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use settings::{
+    BaseKeymapContent, Settings, SettingsError, SettingsStore, TerminalExitBehavior, paths,
+};
+use terminal::{Event, TerminalBuilder, terminal_settings::TerminalSettings};
+use terminal_view::TerminalView;
+use util::{paths::PathStyle, shell::Shell};
+
+use crate::{
+    cli::Args,
+    keymap,
+    pane_group::{PaneColors, PaneGroup, SplitDirection},
+    status_bar, themes,
+};
+
+/// How far a single resize action moves a divider, as a fraction of the split.
+const PANE_RESIZE_STEP: f32 = 0.05;
+/// How long to wait for a burst of file events from a single save to settle.
+const CONFIG_RELOAD_DEBOUNCE: Duration = Duration::from_millis(50);
+
+actions!(
+    alacrterm,
+    [
+        /// Opens a new terminal tab next to the active one.
+        NewTab,
+        /// Closes the active terminal tab.
+        CloseTab,
+        /// Activates the next terminal tab.
+        NextTab,
+        /// Activates the previous terminal tab.
+        PreviousTab,
+        /// Splits the active pane and opens a new terminal to its right.
+        SplitRight,
+        /// Splits the active pane and opens a new terminal below it.
+        SplitDown,
+        /// Closes the active pane, closing the tab along with its last pane.
+        ClosePane,
+        /// Focuses the pane to the left of the active one.
+        ActivatePaneLeft,
+        /// Focuses the pane to the right of the active one.
+        ActivatePaneRight,
+        /// Focuses the pane above the active one.
+        ActivatePaneUp,
+        /// Focuses the pane below the active one.
+        ActivatePaneDown,
+        /// Moves the nearest vertical divider of the active pane to the left.
+        ResizePaneLeft,
+        /// Moves the nearest vertical divider of the active pane to the right.
+        ResizePaneRight,
+        /// Moves the nearest horizontal divider of the active pane up.
+        ResizePaneUp,
+        /// Moves the nearest horizontal divider of the active pane down.
+        ResizePaneDown,
+        /// Gives all panes in the active tab an equal share of the space.
+        ResetPaneSizes,
+        /// Toggles whether the active pane fills the whole tab.
+        TogglePaneZoom,
+    ]
+);
+
+/// Activates the terminal tab at the given (zero-based) index.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Action)]
+#[action(namespace = alacrterm)]
+pub struct ActivateTab(pub usize);
+
+struct Tab {
+    panes: PaneGroup,
+    active_pane: Entity<TerminalView>,
+    zoomed: bool,
+}
+
+/// Where a terminal that is still being spawned ends up once it is ready.
+enum NewPanePlacement {
+    Tab,
+    Split(WeakEntity<TerminalView>, SplitDirection),
+}
+
+pub struct TerminalApp {
+    tabs: Vec<Tab>,
+    active_tab: usize,
+    pane_subscriptions: HashMap<EntityId, Vec<Subscription>>,
+    error: Option<String>,
+    /// Keeps every terminal open after its process exits, regardless of `on_exit`.
+    hold: bool,
+    /// The bindings of the user keymap that failed to load.
+    keymap_errors: Vec<SettingsError>,
+    /// The `base_keymap` setting the keys were last bound with.
+    base_keymap: Option<BaseKeymapContent>,
+    /// The settings errors the user closed the banner for.
+    dismissed_settings_errors: Vec<SettingsError>,
+    /// The title last given to the window, to only update it when it changes.
+    window_title: SharedString,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl TerminalApp {
+    pub fn new(args: &Args, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let settings_shell = TerminalSettings::get_global(cx).shell;
+        let shell = args.shell(settings_shell.clone());
+        let builder = Self::default_builder(args.working_directory(), shell, window, cx);
+        // `--command` is only meant for the first terminal, the ones opened from it run the shell
+        // from the settings.
+        let builder = cx.background_spawn(async move {
+            anyhow::Ok(builder.await?.with_template_shell(settings_shell))
+        });
+        let mut this = Self {
+            tabs: Vec::new(),
+            active_tab: 0,
+            pane_subscriptions: HashMap::default(),
+            error: None,
+            hold: args.hold,
+            keymap_errors: Vec::new(),
+            base_keymap: None,
+            dismissed_settings_errors: Vec::new(),
+            window_title: SharedString::default(),
+            _subscriptions: vec![
+                cx.observe_global::<SettingsStore>(|this, cx| {
+                    let base_keymap = cx.global::<SettingsStore>().global_settings().base_keymap;
+                    if base_keymap != this.base_keymap {
+                        this.reload_keymap(cx);
+                    }
+                    cx.notify();
+                }),
+                cx.observe_window_appearance(window, |_, window, cx| {
+                    themes::set_system_appearance(window.appearance(), cx);
+                }),
+            ],
+        };
+        themes::set_system_appearance(window.appearance(), cx);
+        let title = args.default_shell_title();
+        this.spawn_terminal(builder, NewPanePlacement::Tab, title, window, cx);
+        this.reload_keymap(cx);
+        if let Some(path) = args.settings_file() {
+            Self::watch_config_file(path, Self::reload_settings, cx);
+        }
+        if let Some(path) = paths::keymap_file() {
+            Self::watch_config_file(path, |this, _, cx| this.reload_keymap(cx), cx);
+        }
+        this
+    }
+
+    /// Calls `reload` whenever the configuration file at `path` changes on disk.
+    fn watch_config_file(
+        path: PathBuf,
+        reload: fn(&mut Self, &Path, &mut Context<Self>),
+        cx: &mut Context<Self>,
+    ) {
+        // The directory is watched, so it has to exist even before the file does.
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).ok();
+        }
+        let (watcher, changes) = match settings::watch_config_file(path.clone()) {
+            Ok(watch) => watch,
+            Err(error) => {
+                log::error!("failed to watch {}: {error}", path.display());
+                return;
+            }
+        };
+
+        cx.spawn(async move |app, cx| {
+            let _watcher = watcher;
+            while changes.recv().await.is_ok() {
+                cx.background_executor().timer(CONFIG_RELOAD_DEBOUNCE).await;
+                while changes.try_recv().is_ok() {}
+                app.update(cx, |app, cx| reload(app, &path, cx))?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Re-reads the user settings; open terminals pick up the change by observing the store.
+    fn reload_settings(&mut self, path: &Path, cx: &mut Context<Self>) {
+        // Keep the last good settings while the file is briefly missing during a save.
+        if !path.exists() {
+            return;
+        }
+        let result =
+            cx.update_global::<SettingsStore, _>(|store, _| store.load_user_settings(path));
+        if let Err(error) = result {
+            log::error!("failed to reload settings: {error}");
+        }
+        cx.notify();
+    }
+
+    /// Rebinds the keys from the default keymap and the user keymap, if there is one.
+    fn reload_keymap(&mut self, cx: &mut Context<Self>) {
+        self.base_keymap = cx.global::<SettingsStore>().global_settings().base_keymap;
+        self.keymap_errors = keymap::load_keymaps(paths::keymap_file().as_deref(), cx);
+        cx.notify();
+    }
+
+    fn settings_errors(&self, cx: &App) -> Vec<SettingsError> {
+        cx.global::<SettingsStore>()
+            .errors()
+            .chain(&self.keymap_errors)
+            .cloned()
+            .collect()
+    }
+
+    fn dismiss_settings_errors(&mut self, cx: &mut Context<Self>) {
+        self.dismissed_settings_errors = self.settings_errors(cx);
+        cx.notify();
+    }
+
+    /// Builds a terminal running `shell` in `working_directory`, following the global settings.
+    fn default_builder(
+        working_directory: Option<PathBuf>,
+        shell: Shell,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<TerminalBuilder>> {
+        let terminal_settings = TerminalSettings::get_global(cx).clone();
+        TerminalBuilder::new(
+            working_directory,
+            shell,
+            terminal_settings.env,
+            terminal_settings.cursor_shape,
+            terminal_settings.alternate_scroll,
+            terminal_settings.max_scroll_history_lines,
+            terminal_settings.path_hyperlink_regexes,
+            terminal_settings.path_hyperlink_timeout_ms,
+            false,
+            window.window_handle().window_id().as_u64(),
+            None,
+            cx,
+            Vec::new(),
+            PathStyle::local(),
+        )
+    }
+
+    /// Builds a terminal with the same shell and working directory as `pane`.
+    fn clone_builder(
+        pane: &Entity<TerminalView>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<TerminalBuilder>> {
+        pane.read(cx)
+            .terminal()
+            .read(cx)
+            .clone_builder(cx, std::env::current_dir().ok())
+    }
+
+    /// Waits for `builder` and places the resulting terminal according to `placement`, titled
+    /// `title` instead of after its process if given.
+    fn spawn_terminal(
+        &mut self,
+        builder: Task<Result<TerminalBuilder>>,
+        placement: NewPanePlacement,
+        title: Option<String>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        cx.spawn_in(window, async move |app, cx| {
+            let builder = match builder.await {
+                Ok(builder) => builder,
+                Err(error) => {
+                    app.update(cx, |app, cx| {
+                        if app.tabs.is_empty() {
+                            app.error = Some(error.to_string());
+                        }
+                        cx.notify();
+                    })?;
+                    return Err(error);
+                }
+            };
+
+            app.update_in(cx, |app, window, cx| {
+                let terminal = cx.new(|cx| builder.subscribe(cx));
+                let pane = cx.new(|cx| {
+                    let mut view = TerminalView::new(terminal, window, cx);
+                    view.set_custom_title(title, cx);
+                    view
+                });
+                app.add_pane(pane, placement, window, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn add_pane(
+        &mut self,
+        pane: Entity<TerminalView>,
+        placement: NewPanePlacement,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.subscribe_to_pane(&pane, window, cx);
+        self.error = None;
+
+        if let NewPanePlacement::Split(target, direction) = placement
+            && let Some(target) = target.upgrade()
+            && let Some(tab_ix) = self.tab_for_pane(&target)
+        {
+            let tab = &mut self.tabs[tab_ix];
+            tab.panes.split(&target, pane.clone(), direction);
+            tab.zoomed = false;
+            self.active_tab = tab_ix;
+            self.activate_pane(&pane, window, cx);
+            return;
+        }
+
+        // Splits whose pane went away in the meantime open as a tab instead.
+        let index = if self.tabs.is_empty() {
+            0
+        } else {
+            self.active_tab + 1
+        };
+        self.tabs.insert(
+            index,
+            Tab {
+                panes: PaneGroup::new(pane.clone()),
+                active_pane: pane,
+                zoomed: false,
+            },
+        );
+        self.activate_tab(index, window, cx);
+    }
+
+    fn subscribe_to_pane(
+        &mut self,
+        pane: &Entity<TerminalView>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let focus_handle = pane.focus_handle(cx);
+        let weak_pane = pane.downgrade();
+        let subscriptions = vec![
+            cx.subscribe_in(
+                pane,
+                window,
+                |app, pane, event: &Event, window, cx| match event {
+                    Event::TitleChanged | Event::BreadcrumbsChanged => cx.notify(),
+                    Event::CloseTerminal => app.terminal_exited(pane, window, cx),
+                    _ => {}
+                },
+            ),
+            // The status bar follows the state of the terminal.
+            cx.observe(pane, |_, _, cx| cx.notify()),
+            cx.on_focus_in(&focus_handle, window, move |app, _, cx| {
+                if let Some(pane) = weak_pane.upgrade() {
+                    app.pane_focused(&pane, cx);
+                }
+            }),
+        ];
+        self.pane_subscriptions
+            .insert(pane.entity_id(), subscriptions);
+    }
+
+    fn tab_for_pane(&self, pane: &Entity<TerminalView>) -> Option<usize> {
+        self.tabs.iter().position(|tab| tab.panes.contains(pane))
+    }
+
+    fn active_pane(&self) -> Option<&Entity<TerminalView>> {
+        self.tabs.get(self.active_tab).map(|tab| &tab.active_pane)
+    }
+
+    /// Keeps the active tab and pane in sync when a pane is focused, e.g. by clicking into it.
+    fn pane_focused(&mut self, pane: &Entity<TerminalView>, cx: &mut Context<Self>) {
+        if let Some(tab_ix) = self.tab_for_pane(pane) {
+            self.active_tab = tab_ix;
+            self.tabs[tab_ix].active_pane = pane.clone();
+            cx.notify();
+        }
+    }
+
+    fn activate_pane(
+        &mut self,
+        pane: &Entity<TerminalView>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(tab_ix) = self.tab_for_pane(pane) else {
+            return;
+        };
+        self.active_tab = tab_ix;
+        self.tabs[tab_ix].active_pane = pane.clone();
+        window.focus(&pane.focus_handle(cx), cx);
+        cx.notify();
+    }
+
+    fn activate_tab(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(tab) = self.tabs.get(index) else {
+            return;
+        };
+        self.active_tab = index;
+        window.focus(&tab.active_pane.focus_handle(cx), cx);
+        cx.notify();
+    }
+
+    fn new_tab(&mut self, _: &NewTab, window: &mut Window, cx: &mut Context<Self>) {
+        let builder = match self.active_pane().cloned() {
+            Some(pane) => Self::clone_builder(&pane, cx),
+            None => {
+                let shell = TerminalSettings::get_global(cx).shell;
+                Self::default_builder(std::env::current_dir().ok(), shell, window, cx)
+            }
+        };
+        self.spawn_terminal(builder, NewPanePlacement::Tab, None, window, cx);
+    }
+
+    fn close_tab(&mut self, _: &CloseTab, window: &mut Window, cx: &mut Context<Self>) {
+        self.remove_tab(self.active_tab, window, cx);
+    }
+
+    fn remove_tab(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        if index >= self.tabs.len() {
+            return;
+        }
+        let tab = self.tabs.remove(index);
+        for pane in tab.panes.panes() {
+            self.pane_subscriptions.remove(&pane.entity_id());
+        }
+        if self.tabs.is_empty() {
+            window.remove_window();
+            return;
+        }
+
+        if self.active_tab > index || self.active_tab == self.tabs.len() {
+            self.active_tab -= 1;
+        }
+        self.activate_tab(self.active_tab, window, cx);
+    }
+
+    fn next_tab(&mut self, _: &NextTab, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.tabs.is_empty() {
+            self.activate_tab((self.active_tab + 1) % self.tabs.len(), window, cx);
+        }
+    }
+
+    fn previous_tab(&mut self, _: &PreviousTab, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.tabs.is_empty() {
+            let index = self
+                .active_tab
+                .checked_sub(1)
+                .unwrap_or(self.tabs.len() - 1);
+            self.activate_tab(index, window, cx);
+        }
+    }
+
+    fn activate_tab_action(
+        &mut self,
+        action: &ActivateTab,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.activate_tab(action.0, window, cx);
+    }
+
+    fn split(&mut self, direction: SplitDirection, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(pane) = self.active_pane().cloned() else {
+            return;
+        };
+        let builder = Self::clone_builder(&pane, cx);
+        self.spawn_terminal(
+            builder,
+            NewPanePlacement::Split(pane.downgrade(), direction),
+            None,
+            window,
+            cx,
+        );
+    }
+
+    fn split_right(&mut self, _: &SplitRight, window: &mut Window, cx: &mut Context<Self>) {
+        self.split(SplitDirection::Right, window, cx);
+    }
+
+    fn split_down(&mut self, _: &SplitDown, window: &mut Window, cx: &mut Context<Self>) {
+        self.split(SplitDirection::Down, window, cx);
+    }
+
+    fn close_active_pane(&mut self, _: &ClosePane, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(pane) = self.active_pane().cloned() {
+            self.close_pane(&pane, window, cx);
+        }
+    }
+
+    fn close_pane(
+        &mut self,
+        pane: &Entity<TerminalView>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(tab_ix) = self.tab_for_pane(pane) else {
+            return;
+        };
+        let tab = &mut self.tabs[tab_ix];
+        let pane_ix = tab
+            .panes
+            .panes()
+            .iter()
+            .position(|candidate| *candidate == pane)
+            .unwrap_or_default();
+        if !tab.panes.remove(pane) {
+            self.remove_tab(tab_ix, window, cx);
+            return;
+        }
+        self.pane_subscriptions.remove(&pane.entity_id());
+
+        tab.zoomed = false;
+        if &tab.active_pane == pane {
+            let panes = tab.panes.panes();
+            tab.active_pane = panes[pane_ix.saturating_sub(1).min(panes.len() - 1)].clone();
+        }
+        if tab_ix == self.active_tab {
+            self.activate_tab(tab_ix, window, cx);
+        } else {
+            cx.notify();
+        }
+    }
+
+    fn terminal_exited(
+        &mut self,
+        pane: &Entity<TerminalView>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let on_exit = if self.hold {
+            TerminalExitBehavior::Hold
+        } else {
+            TerminalSettings::get_global(cx).on_exit
+        };
+        match on_exit {
+            TerminalExitBehavior::ClosePane => self.close_pane(pane, window, cx),
+            TerminalExitBehavior::CloseTab => {
+                if let Some(tab_ix) = self.tab_for_pane(pane) {
+                    self.remove_tab(tab_ix, window, cx);
+                }
+            }
+            TerminalExitBehavior::CloseWindow => window.remove_window(),
+            TerminalExitBehavior::Hold => pane.update(cx, |pane, cx| pane.hold(cx)),
+        }
+    }
+
+    fn activate_pane_in_direction(
+        &mut self,
+        direction: SplitDirection,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(tab) = self.tabs.get(self.active_tab) else {
+            return;
+        };
+        if tab.zoomed {
+            return;
+        }
+        if let Some(pane) = tab
+            .panes
+            .find_pane_in_direction(&tab.active_pane, direction)
+            .cloned()
+        {
+            self.activate_pane(&pane, window, cx);
+        }
+    }
+
+    fn activate_pane_left(
+        &mut self,
+        _: &ActivatePaneLeft,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.activate_pane_in_direction(SplitDirection::Left, window, cx);
+    }
+
+    fn activate_pane_right(
+        &mut self,
+        _: &ActivatePaneRight,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.activate_pane_in_direction(SplitDirection::Right, window, cx);
+    }
+
+    fn activate_pane_up(
+        &mut self,
+        _: &ActivatePaneUp,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.activate_pane_in_direction(SplitDirection::Up, window, cx);
+    }
+
+    fn activate_pane_down(
+        &mut self,
+        _: &ActivatePaneDown,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.activate_pane_in_direction(SplitDirection::Down, window, cx);
+    }
+
+    fn resize_active_pane(&mut self, axis: Axis, amount: f32, cx: &mut Context<Self>) {
+        let Some(tab) = self.tabs.get_mut(self.active_tab) else {
+            return;
+        };
+        let pane = tab.active_pane.clone();
+        if tab.panes.resize(&pane, axis, amount) {
+            cx.notify();
+        }
+    }
+
+    fn resize_pane_left(&mut self, _: &ResizePaneLeft, _: &mut Window, cx: &mut Context<Self>) {
+        self.resize_active_pane(Axis::Horizontal, -PANE_RESIZE_STEP, cx);
+    }
+
+    fn resize_pane_right(&mut self, _: &ResizePaneRight, _: &mut Window, cx: &mut Context<Self>) {
+        self.resize_active_pane(Axis::Horizontal, PANE_RESIZE_STEP, cx);
+    }
+
+    fn resize_pane_up(&mut self, _: &ResizePaneUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.resize_active_pane(Axis::Vertical, -PANE_RESIZE_STEP, cx);
+    }
+
+    fn resize_pane_down(&mut self, _: &ResizePaneDown, _: &mut Window, cx: &mut Context<Self>) {
+        self.resize_active_pane(Axis::Vertical, PANE_RESIZE_STEP, cx);
+    }
+
+    fn reset_pane_sizes(&mut self, _: &ResetPaneSizes, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+            tab.panes.reset_sizes();
+            cx.notify();
+        }
+    }
+
+    fn toggle_pane_zoom(
+        &mut self,
+        _: &TogglePaneZoom,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(tab) = self.tabs.get_mut(self.active_tab) else {
+            return;
+        };
+        tab.zoomed = !tab.zoomed && tab.panes.panes().len() > 1;
+        let pane = tab.active_pane.clone();
+        window.focus(&pane.focus_handle(cx), cx);
+        cx.notify();
+    }
+
+    fn tab_title(terminal_view: &Entity<TerminalView>, cx: &App) -> SharedString {
+        let terminal_view = terminal_view.read(cx);
+        terminal_view
+            .custom_title()
+            .map(|title| title.to_string())
+            .unwrap_or_else(|| terminal_view.terminal().read(cx).title(true))
+            .into()
+    }
+
+    /// The title of the active terminal, following the `title` setting.
+    fn title(&self, cx: &App) -> SharedString {
+        self.active_pane()
+            .map(|pane| pane.read(cx).window_title(cx).into())
+            .unwrap_or_else(|| "alacrterm".into())
+    }
+
+    fn render_settings_errors(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let errors = self.settings_errors(cx);
+        if errors.is_empty() || errors == self.dismissed_settings_errors {
+            return None;
+        }
+        let colors = &cx.theme().colors;
+
+        Some(
+            h_flex()
+                .w_full()
+                .px_3()
+                .py_1()
+                .gap_2()
+                .items_start()
+                .bg(colors.danger)
+                .text_color(colors.danger_foreground)
+                .child(
+                    v_flex()
+                        .flex_grow()
+                        .min_w_0()
+                        .child("Failed to load some settings, the last valid ones stay in effect:")
+                        .children(errors.iter().map(|error| error.to_string())),
+                )
+                .child(div().px_1().child("×").on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(|app, _, _, cx| app.dismiss_settings_errors(cx)),
+                )),
+        )
+    }
+
+    fn render_tab_strip(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let colors = &cx.theme().colors;
+        let (tab_bg, tab_active_bg, tab_fg, tab_active_fg, border) = (
+            colors.tab,
+            colors.tab_active,
+            colors.tab_foreground,
+            colors.tab_active_foreground,
+            colors.border,
+        );
+
+        h_flex()
+            .min_w_0()
+            .gap_1()
+            .children(self.tabs.iter().enumerate().map(|(index, tab)| {
+                let is_active = index == self.active_tab;
+                h_flex()
+                    .gap_2()
+                    .px_3()
+                    .py_0p5()
+                    .max_w_48()
+                    .rounded_md()
+                    .border_1()
+                    .border_color(border)
+                    .text_sm()
+                    .bg(if is_active { tab_active_bg } else { tab_bg })
+                    .text_color(if is_active { tab_active_fg } else { tab_fg })
+                    .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+                        cx.stop_propagation();
+                        window.dispatch_action(Box::new(ActivateTab(index)), cx);
+                    })
+                    .child(
+                        div()
+                            .min_w_0()
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .whitespace_nowrap()
+                            .child(Self::tab_title(&tab.active_pane, cx)),
+                    )
+                    .child(div().child("×").on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |app, _, window, cx| {
+                            cx.stop_propagation();
+                            app.remove_tab(index, window, cx);
+                        }),
+                    ))
+            }))
+            .child(
+                div()
+                    .px_2()
+                    .child("+")
+                    .on_mouse_down(MouseButton::Left, |_, window, cx| {
+                        cx.stop_propagation();
+                        window.dispatch_action(Box::new(NewTab), cx);
+                    }),
+            )
+    }
+}
+
+impl Render for TerminalApp {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let title = self.title(cx);
+        if title != self.window_title {
+            window.set_window_title(&title);
+            self.window_title = title.clone();
+        }
+
+        let colors = &cx.theme().colors;
+        let pane_colors = PaneColors {
+            divider: colors.border,
+            divider_hover: colors.primary,
+            active_border: colors.primary,
+        };
+        let title_color = colors.muted_foreground;
+
+        let content = if let Some(tab) = self.tabs.get(self.active_tab) {
+            if tab.zoomed {
+                tab.active_pane.clone().into_any_element()
+            } else {
+                tab.panes.render(&tab.active_pane, pane_colors)
+            }
+        } else if let Some(error) = &self.error {
+            div()
+                .size_full()
+                .flex()
+                .items_center()
+                .justify_center()
+                .child(format!("Failed to start terminal: {error}"))
+                .into_any_element()
+        } else {
+            div()
+                .size_full()
+                .flex()
+                .items_center()
+                .justify_center()
+                .child("Starting terminal...")
+                .into_any_element()
+        };
+
+        v_flex()
+            .size_full()
+            .on_action(cx.listener(Self::new_tab))
+            .on_action(cx.listener(Self::close_tab))
+            .on_action(cx.listener(Self::next_tab))
+            .on_action(cx.listener(Self::previous_tab))
+            .on_action(cx.listener(Self::activate_tab_action))
+            .on_action(cx.listener(Self::split_right))
+            .on_action(cx.listener(Self::split_down))
+            .on_action(cx.listener(Self::close_active_pane))
+            .on_action(cx.listener(Self::activate_pane_left))
+            .on_action(cx.listener(Self::activate_pane_right))
+            .on_action(cx.listener(Self::activate_pane_up))
+            .on_action(cx.listener(Self::activate_pane_down))
+            .on_action(cx.listener(Self::resize_pane_left))
+            .on_action(cx.listener(Self::resize_pane_right))
+            .on_action(cx.listener(Self::resize_pane_up))
+            .on_action(cx.listener(Self::resize_pane_down))
+            .on_action(cx.listener(Self::reset_pane_sizes))
+            .on_action(cx.listener(Self::toggle_pane_zoom))
+            .child(
+                TitleBar::new().child(
+                    h_flex()
+                        .w_full()
+                        .pr_2()
+                        .gap_2()
+                        .justify_between()
+                        .child(self.render_tab_strip(cx))
+                        .child(
+                            div()
+                                .min_w_0()
+                                .overflow_hidden()
+                                .text_ellipsis()
+                                .whitespace_nowrap()
+                                .text_sm()
+                                .text_color(title_color)
+                                .child(title),
+                        ),
+                ),
+            )
+            .children(self.render_settings_errors(cx))
+            .child(
+                div()
+                    .id("terminal-container")
+                    .flex_grow()
+                    .min_h_0()
+                    .w_full()
+                    .overflow_hidden()
+                    .child(content),
+            )
+            .children(
+                self.active_pane()
+                    .and_then(|pane| status_bar::render(pane, cx)),
+            )
+    }
+}
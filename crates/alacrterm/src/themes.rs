@@ -1,15 +1,333 @@
-use gpui::{App, SharedString};
-use gpui_component::{Theme, ThemeRegistry};
-use std::path::PathBuf;
-
-pub fn set_theme(cx: &mut App, name: impl Into<SharedString>) {
-    let theme_name = name.into();
-    // Load and watch themes from ./themes directory
-    if let Err(err) = ThemeRegistry::watch_dir(PathBuf::from("./themes"), cx, move |cx| {
-        if let Some(theme) = ThemeRegistry::global(cx).themes().get(&theme_name).cloned() {
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use anyhow::{Context as _, Result};
+use gpui::{App, Global, PathPromptOptions, SharedString, WindowAppearance, actions};
+use gpui_component::{Theme, ThemeConfig, ThemeMode, ThemeRegistry, ThemeSet};
+use rust_embed::RustEmbed;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use settings::{
+    DEFAULT_DARK_THEME, DEFAULT_LIGHT_THEME, SettingsStore, ThemeAppearanceMode,
+    ThemeColorsContent, ThemeName, ThemeSelection, paths,
+};
+use terminal::{ThemeColors, color_scheme::ColorScheme};
+
+actions!(
+    alacrterm,
+    [
+        /// Imports iTerm2, Windows Terminal, base16 or Alacritty color schemes as themes.
+        ImportColorScheme,
+        /// Switches between the light and dark theme, saving the choice to the settings file.
+        ToggleThemeMode,
+    ]
+);
+
+/// The themes shipped with the binary, so they are available wherever it is launched from.
+#[derive(RustEmbed)]
+#[folder = "../../themes"]
+#[include = "*.json"]
+struct BundledThemes;
+
+struct ThemeState {
+    bundled: HashMap<SharedString, Rc<ThemeConfig>>,
+    /// The `terminal.*` colors of each theme, which gpui-component does not keep.
+    terminal_colors: HashMap<SharedString, ThemeColorsContent>,
+    /// The window's appearance, followed by themes in `"mode": "system"`.
+    system_mode: ThemeMode,
+}
+
+impl Global for ThemeState {}
+
+/// The subset of a theme file holding the terminal palette.
+#[derive(Deserialize)]
+struct TerminalThemeSet {
+    themes: Vec<TerminalTheme>,
+}
+
+#[derive(Deserialize)]
+struct TerminalTheme {
+    name: SharedString,
+    #[serde(default)]
+    colors: ThemeColorsContent,
+}
+
+fn themes_dir() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("themes"))
+}
+
+pub fn init(cx: &mut App) {
+    cx.set_global(ThemeState {
+        bundled: load_bundled_themes(),
+        terminal_colors: load_terminal_colors(),
+        system_mode: ThemeMode::Dark,
+    });
+
+    // Themes in the user's themes directory take precedence over the bundled ones.
+    match themes_dir() {
+        Some(themes_dir) => {
+            fs::create_dir_all(&themes_dir).ok();
+            if let Err(err) = ThemeRegistry::watch_dir(themes_dir, cx, user_themes_changed) {
+                log::error!("failed to watch themes directory: {err}");
+            }
+        }
+        None => log::warn!("there is no configuration directory, only loading bundled themes"),
+    }
+
+    cx.observe_global::<SettingsStore>(apply_theme).detach();
+    cx.on_action(import_color_scheme);
+    cx.on_action(toggle_theme_mode);
+    apply_theme(cx);
+}
+
+fn load_bundled_themes() -> HashMap<SharedString, Rc<ThemeConfig>> {
+    BundledThemes::iter()
+        .filter_map(|path| {
+            let file = BundledThemes::get(&path)?;
+            serde_json::from_slice::<ThemeSet>(&file.data)
+                .inspect_err(|err| log::error!("failed to parse bundled theme {path}: {err}"))
+                .ok()
+        })
+        .flat_map(|theme_set| theme_set.themes)
+        .map(|theme| (theme.name.clone(), Rc::new(theme)))
+        .collect()
+}
+
+/// Reads the terminal palettes of the bundled themes and then of the user's themes, so the
+/// latter win for themes with the same name.
+fn load_terminal_colors() -> HashMap<SharedString, ThemeColorsContent> {
+    let bundled = BundledThemes::iter()
+        .filter_map(|path| BundledThemes::get(&path).map(|file| file.data.into_owned()));
+    let user = themes_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .filter_map(|path| fs::read(path).ok());
+    bundled
+        .chain(user)
+        .filter_map(|content| serde_json::from_slice::<TerminalThemeSet>(&content).ok())
+        .flat_map(|theme_set| theme_set.themes)
+        .map(|theme| (theme.name, theme.colors))
+        .collect()
+}
+
+fn user_themes_changed(cx: &mut App) {
+    cx.global_mut::<ThemeState>().terminal_colors = load_terminal_colors();
+    apply_theme(cx);
+}
+
+/// Switches between the light and dark theme of a `"mode": "system"` selection to match the
+/// window's appearance.
+pub fn set_system_appearance(appearance: WindowAppearance, cx: &mut App) {
+    let mode = match appearance {
+        WindowAppearance::Light | WindowAppearance::VibrantLight => ThemeMode::Light,
+        WindowAppearance::Dark | WindowAppearance::VibrantDark => ThemeMode::Dark,
+    };
+    if cx.global::<ThemeState>().system_mode != mode {
+        cx.global_mut::<ThemeState>().system_mode = mode;
+        apply_theme(cx);
+    }
+}
+
+fn theme_selection(cx: &App) -> ThemeSelection {
+    cx.global::<SettingsStore>()
+        .global_settings()
+        .theme
+        .theme
+        .clone()
+        .unwrap_or_default()
+}
+
+/// Resolves `"mode": "system"` to the window's appearance.
+fn theme_mode(mode: ThemeAppearanceMode, cx: &App) -> ThemeMode {
+    match mode {
+        ThemeAppearanceMode::Light => ThemeMode::Light,
+        ThemeAppearanceMode::Dark => ThemeMode::Dark,
+        ThemeAppearanceMode::System => cx.global::<ThemeState>().system_mode,
+    }
+}
+
+fn find_theme(name: &ThemeName, cx: &App) -> Option<Rc<ThemeConfig>> {
+    ThemeRegistry::global(cx)
+        .themes()
+        .get(name.0.as_ref())
+        .or_else(|| cx.global::<ThemeState>().bundled.get(name.0.as_ref()))
+        .cloned()
+}
+
+/// Applies the theme selected by the `theme` setting.
+fn apply_theme(cx: &mut App) {
+    let name = match theme_selection(cx) {
+        ThemeSelection::Static(name) => name,
+        ThemeSelection::Dynamic { mode, light, dark } => match theme_mode(mode, cx) {
+            ThemeMode::Light => light,
+            ThemeMode::Dark => dark,
+        },
+    };
+
+    match find_theme(&name, cx) {
+        Some(theme) => {
             Theme::global_mut(cx).apply_config(&theme);
+            cx.set_global(terminal_colors(&theme, cx));
+            cx.refresh_windows();
+        }
+        None => log::warn!("theme {:?} not found", name.0),
+    }
+}
+
+/// Returns the terminal palette of `theme`, refined off of the defaults for its mode.
+fn terminal_colors(theme: &ThemeConfig, cx: &App) -> ThemeColors {
+    let mut colors = match theme.mode {
+        ThemeMode::Light => ThemeColors::light(),
+        ThemeMode::Dark => ThemeColors::dark(),
+    };
+    if let Some(content) = cx.global::<ThemeState>().terminal_colors.get(&theme.name) {
+        colors.refine(content);
+    }
+    colors
+}
+
+fn toggle_theme_mode(_: &ToggleThemeMode, cx: &mut App) {
+    let selection = match theme_selection(cx) {
+        ThemeSelection::Dynamic { mode, light, dark } => ThemeSelection::Dynamic {
+            mode: match theme_mode(mode, cx) {
+                ThemeMode::Light => ThemeAppearanceMode::Dark,
+                ThemeMode::Dark => ThemeAppearanceMode::Light,
+            },
+            light,
+            dark,
+        },
+        // Pair a single theme with the default theme of the other mode.
+        ThemeSelection::Static(name) => match find_theme(&name, cx).map(|theme| theme.mode) {
+            Some(ThemeMode::Light) => ThemeSelection::Dynamic {
+                mode: ThemeAppearanceMode::Dark,
+                light: name,
+                dark: ThemeName(DEFAULT_DARK_THEME.into()),
+            },
+            _ => ThemeSelection::Dynamic {
+                mode: ThemeAppearanceMode::Light,
+                light: ThemeName(DEFAULT_LIGHT_THEME.into()),
+                dark: name,
+            },
+        },
+    };
+    // Observing the store applies the new theme.
+    cx.update_global::<SettingsStore, _>(|store, cx| {
+        if let Err(err) = store.update_user_settings(cx, |settings| {
+            settings.theme.theme = Some(selection);
+        }) {
+            log::error!("failed to save the theme: {err}");
         }
-    }) {
-        println!("Failed to watch themes directory: {}", err);
+    });
+}
+
+fn import_color_scheme(_: &ImportColorScheme, cx: &mut App) {
+    let paths = cx.prompt_for_paths(PathPromptOptions {
+        files: true,
+        directories: false,
+        multiple: true,
+        prompt: Some("Import".into()),
+    });
+    cx.spawn(async move |_| {
+        let Some(paths) = paths.await?? else {
+            return anyhow::Ok(());
+        };
+        // The new theme files are picked up by the themes directory watcher.
+        for path in paths {
+            match import_color_scheme_file(&path) {
+                Ok(theme_path) => log::info!("imported {path:?} as {theme_path:?}"),
+                Err(err) => log::error!("{err:#}"),
+            }
+        }
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Writes the color scheme at `path` as a theme file in the themes directory. Existing themes are
+/// kept: if the scheme's file name is taken, it is numbered.
+fn import_color_scheme_file(path: &Path) -> Result<PathBuf> {
+    let themes_dir = themes_dir().context("there is no configuration directory to import into")?;
+    let scheme = ColorScheme::load(path)?;
+    let content = scheme.colors.to_content();
+    let mut colors = json!({
+        // Match the window's colors to the terminal's.
+        "background": content.terminal_background,
+        "foreground": content.terminal_foreground,
+    });
+    if let (Some(colors), Value::Object(terminal_colors)) =
+        (colors.as_object_mut(), serde_json::to_value(&content)?)
+    {
+        colors.extend(
+            terminal_colors
+                .into_iter()
+                .filter(|(_, color)| !color.is_null()),
+        );
+    }
+
+    // Fall back to the source file's name for schemes whose name can't name a file.
+    let file_stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (name, file_name) = [&scheme.name, &file_stem]
+        .into_iter()
+        .map(|name| (name.trim(), theme_file_name(name)))
+        .find(|(_, file_name)| file_name.chars().any(char::is_alphanumeric))
+        .unwrap_or(("Color Scheme", "color_scheme".to_string()));
+
+    let mut number = 1;
+    loop {
+        let (theme_name, theme_file) = match number {
+            1 => (name.to_string(), format!("{file_name}.json")),
+            _ => (
+                format!("{name} {number}"),
+                format!("{file_name}_{number}.json"),
+            ),
+        };
+        let theme_path = themes_dir.join(theme_file);
+        let file = match fs::File::create_new(&theme_path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                number += 1;
+                continue;
+            }
+            Err(err) => return Err(err).with_context(|| format!("failed to write {theme_path:?}")),
+        };
+        let theme_set = json!({
+            "name": theme_name,
+            "themes": [{
+                "name": theme_name,
+                "mode": if scheme.is_light() { "light" } else { "dark" },
+                "colors": colors,
+            }],
+        });
+        serde_json::to_writer_pretty(file, &theme_set)
+            .with_context(|| format!("failed to write {theme_path:?}"))?;
+        return Ok(theme_path);
     }
 }
+
+/// Lowercases `name` and replaces everything but letters and digits with underscores.
+fn theme_file_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
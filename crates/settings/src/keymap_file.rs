@@ -0,0 +1,271 @@
+use std::{fs, io, path::Path, rc::Rc};
+
+use gpui::{Action, App, KeyBinding, KeyBindingContextPredicate, NoAction, SharedString};
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde_json::Value;
+use util::asset_str;
+
+use crate::{
+    BaseKeymapContent, SettingsAssets, SettingsError, SettingsStore, parse_json_with_comments,
+};
+
+/// A keymap file: a list of sections, each binding keystrokes to actions in a key context.
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct KeymapFile(Vec<KeymapSection>);
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct KeymapSection {
+    /// A predicate on the key context, such as `Terminal && !vi_mode`. Sections without one apply
+    /// everywhere.
+    pub context: String,
+    /// Whether keystrokes match the key that produces their character on the current keyboard
+    /// layout rather than the physical key.
+    pub use_key_equivalents: bool,
+    pub bindings: IndexMap<String, KeymapAction>,
+}
+
+/// An action name, `[name, argument]` for actions such as `terminal::SendText` that take one, or
+/// `null` to remove the binding.
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct KeymapAction(Value);
+
+impl KeymapAction {
+    fn build(&self, cx: &App) -> Result<(Box<dyn Action>, Option<SharedString>), String> {
+        let (name, input) = match &self.0 {
+            Value::Null => return Ok((NoAction.boxed_clone(), None)),
+            Value::String(name) => (name, None),
+            Value::Array(items) => match items.as_slice() {
+                [Value::String(name), input] => (name, Some(input)),
+                _ => return Err("expected an action name followed by its argument".to_string()),
+            },
+            _ => return Err("expected an action name, [name, argument] or null".to_string()),
+        };
+        let action = cx
+            .build_action(name, input.cloned())
+            .map_err(|error| error.to_string())?;
+        Ok((action, input.map(|input| input.to_string().into())))
+    }
+}
+
+impl KeymapFile {
+    pub fn parse(content: &str) -> serde_json::Result<Self> {
+        parse_json_with_comments(content)
+    }
+
+    /// Builds the key bindings of every section, skipping the ones that fail to load and
+    /// describing them in the returned errors.
+    pub fn key_bindings(&self, cx: &App) -> (Vec<KeyBinding>, Vec<String>) {
+        let mut key_bindings = Vec::new();
+        let mut errors = Vec::new();
+        for section in &self.0 {
+            let context = if section.context.is_empty() {
+                None
+            } else {
+                match KeyBindingContextPredicate::parse(&section.context) {
+                    Ok(predicate) => Some(Rc::new(predicate)),
+                    Err(error) => {
+                        errors.push(format!("invalid context {:?}: {error}", section.context));
+                        continue;
+                    }
+                }
+            };
+
+            for (keystrokes, action) in &section.bindings {
+                let key_binding = action.build(cx).and_then(|(action, input)| {
+                    KeyBinding::load(
+                        keystrokes,
+                        action,
+                        context.clone(),
+                        section.use_key_equivalents,
+                        input,
+                        cx.keyboard_mapper().as_ref(),
+                    )
+                    .map_err(|error| error.to_string())
+                });
+                match key_binding {
+                    Ok(key_binding) => key_bindings.push(key_binding),
+                    Err(error) => errors.push(format!("{keystrokes:?}: {error}")),
+                }
+            }
+        }
+        (key_bindings, errors)
+    }
+}
+
+/// Returns the path of the default keymap for the current platform within [`SettingsAssets`].
+pub fn default_keymap_path() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "keymaps/default-macos.json"
+    } else if cfg!(target_os = "windows") {
+        "keymaps/default-windows.json"
+    } else {
+        "keymaps/default-linux.json"
+    }
+}
+
+/// Returns the path of the keymap for `base_keymap` and the current platform within
+/// [`SettingsAssets`], for base keymaps that add to the default one.
+pub fn base_keymap_path(base_keymap: BaseKeymapContent) -> Option<String> {
+    let name = match base_keymap {
+        BaseKeymapContent::VSCode | BaseKeymapContent::None => return None,
+        BaseKeymapContent::JetBrains => "jetbrains",
+        BaseKeymapContent::SublimeText => "sublime_text",
+        BaseKeymapContent::Atom => "atom",
+        BaseKeymapContent::TextMate => "textmate",
+        BaseKeymapContent::Emacs => "emacs",
+        BaseKeymapContent::Cursor => "cursor",
+    };
+    let platform = if cfg!(target_os = "macos") {
+        "macos"
+    } else {
+        "linux"
+    };
+    Some(format!("keymaps/{platform}/{name}.json"))
+}
+
+/// Loads the key bindings of the default keymap, followed by the ones of the `base_keymap`
+/// setting.
+///
+/// The default keymaps are shared with Zed and bind many actions alacrterm does not have, so
+/// bindings that fail to load are skipped.
+pub fn default_key_bindings(cx: &App) -> Vec<KeyBinding> {
+    let base_keymap = cx
+        .global::<SettingsStore>()
+        .global_settings()
+        .base_keymap
+        .unwrap_or_default();
+    if base_keymap == BaseKeymapContent::None {
+        return Vec::new();
+    }
+    let mut key_bindings = asset_key_bindings(default_keymap_path(), cx);
+    if let Some(path) = base_keymap_path(base_keymap) {
+        key_bindings.extend(asset_key_bindings(&path, cx));
+    }
+    key_bindings
+}
+
+fn asset_key_bindings(path: &str, cx: &App) -> Vec<KeyBinding> {
+    if SettingsAssets::get(path).is_none() {
+        log::warn!("there is no {path} keymap for this platform");
+        return Vec::new();
+    }
+    let keymap = match KeymapFile::parse(&asset_str::<SettingsAssets>(path)) {
+        Ok(keymap) => keymap,
+        Err(error) => {
+            log::error!("failed to parse {path}: {error}");
+            return Vec::new();
+        }
+    };
+    let (key_bindings, errors) = keymap.key_bindings(cx);
+    if !errors.is_empty() {
+        log::warn!(
+            "skipped {} key bindings of {path}: {}",
+            errors.len(),
+            errors.join(", ")
+        );
+    }
+    key_bindings
+}
+
+/// Loads the key bindings of the user keymap at `path`, which does not have to exist.
+///
+/// Bindings that fail to load are skipped and reported, so a typo does not disable the rest.
+pub fn user_key_bindings(path: &Path, cx: &App) -> (Vec<KeyBinding>, Vec<SettingsError>) {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Default::default(),
+        Err(error) => return (Vec::new(), vec![SettingsError::io(path, error)]),
+    };
+    let keymap = match KeymapFile::parse(&content) {
+        Ok(keymap) => keymap,
+        Err(error) => return (Vec::new(), vec![SettingsError::json(path, error)]),
+    };
+    let (key_bindings, errors) = keymap.key_bindings(cx);
+    let errors = errors
+        .into_iter()
+        .map(|message| SettingsError {
+            path: path.to_path_buf(),
+            position: None,
+            message,
+        })
+        .collect();
+    (key_bindings, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keymap_file() {
+        let keymap = KeymapFile::parse(
+            r#"[
+                // Applies everywhere.
+                {
+                    "bindings": {
+                        "ctrl-shift-t": "alacrterm::NewTab",
+                        "ctrl-shift-w": null,
+                    },
+                },
+                {
+                    "context": "Terminal && !vi_mode",
+                    "bindings": {
+                        "alt-b": ["terminal::SendText", "\u001bb"],
+                        "ctrl-b": ["terminal::SendKeystroke", "ctrl-b"],
+                    },
+                },
+            ]"#,
+        )
+        .unwrap();
+
+        let [global, terminal] = keymap.0.as_slice() else {
+            panic!("expected two sections, got {:?}", keymap.0);
+        };
+        assert_eq!(global.context, "");
+        assert_eq!(global.bindings["ctrl-shift-t"].0, "alacrterm::NewTab");
+        assert_eq!(global.bindings["ctrl-shift-w"].0, Value::Null);
+        assert_eq!(terminal.context, "Terminal && !vi_mode");
+        assert_eq!(
+            terminal.bindings["alt-b"].0,
+            serde_json::json!(["terminal::SendText", "\u{1b}b"])
+        );
+    }
+
+    #[test]
+    fn test_default_keymaps_parse() {
+        for path in [
+            "keymaps/default-linux.json",
+            "keymaps/default-macos.json",
+            "keymaps/default-windows.json",
+        ] {
+            let keymap = KeymapFile::parse(&asset_str::<SettingsAssets>(path)).unwrap();
+            assert!(
+                keymap
+                    .0
+                    .iter()
+                    .any(|section| section.bindings.contains_key("ctrl-shift-t")),
+                "{path} has no alacrterm bindings"
+            );
+        }
+    }
+
+    #[test]
+    fn test_base_keymaps_parse() {
+        for base_keymap in [
+            BaseKeymapContent::JetBrains,
+            BaseKeymapContent::SublimeText,
+            BaseKeymapContent::Atom,
+            BaseKeymapContent::Emacs,
+            BaseKeymapContent::Cursor,
+        ] {
+            let path = base_keymap_path(base_keymap).unwrap();
+            KeymapFile::parse(&asset_str::<SettingsAssets>(&path)).unwrap();
+        }
+        assert_eq!(base_keymap_path(BaseKeymapContent::VSCode), None);
+        assert_eq!(base_keymap_path(BaseKeymapContent::None), None);
+    }
+}
@@ -1,26 +1,34 @@
 mod content_into_gpui;
+mod keymap_file;
+pub mod paths;
 mod serde_helper;
 mod settings_content;
+mod settings_error;
+mod settings_json;
+mod settings_schema;
 mod terminal;
 mod theme;
 
 use std::{
     borrow::Cow,
-    error::Error,
     fs,
-    path::Path,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
 use gpui::{App, Global};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
 use rust_embed::RustEmbed;
-use serde::de::DeserializeOwned;
+use serde::{Serialize, de::DeserializeOwned};
 use serde_json::Value;
 use util::{asset_str, strip_json_comments};
 
 pub use content_into_gpui::*;
+pub use keymap_file::*;
 pub use serde_helper::*;
 pub use settings_content::*;
+pub use settings_error::*;
+pub use settings_schema::*;
 pub use terminal::*;
 pub use theme::*;
 
@@ -28,7 +36,15 @@ pub struct SettingsStore {
     // setting_values: TypeIdHashMap<Box<dyn AnySettingValue>>,
     default_settings: Rc<SettingsContent>,
     user_settings: Option<SettingsContent>,
+    /// Where the user settings were loaded from, and where changes to them are written.
+    user_settings_path: Option<PathBuf>,
+    /// Changes waiting to be written to the user settings file, one at a time and in order, so an
+    /// earlier change can't overwrite a later one.
+    user_settings_writes: Option<async_channel::Sender<UserSettingsWrite>>,
+    project_settings: Option<ProjectSettingsContent>,
     global_settings: Rc<SettingsContent>,
+    user_settings_error: Option<SettingsError>,
+    project_settings_error: Option<SettingsError>,
     // extension_settings: Option<Box<SettingsContent>>,
     // server_settings: Option<Box<SettingsContent>>,
 
@@ -48,7 +64,12 @@ impl SettingsStore {
         Ok(Self {
             default_settings: default_settings.clone(),
             user_settings: None,
+            user_settings_path: None,
+            user_settings_writes: None,
+            project_settings: None,
             global_settings: default_settings,
+            user_settings_error: None,
+            project_settings_error: None,
         })
     }
 
@@ -60,37 +81,147 @@ impl SettingsStore {
         self.user_settings.as_ref()
     }
 
+    pub fn project_settings(&self) -> Option<&ProjectSettingsContent> {
+        self.project_settings.as_ref()
+    }
+
     pub fn global_settings(&self) -> &SettingsContent {
         &self.global_settings
     }
 
-    pub fn load_user_settings(
-        &mut self,
-        path: impl AsRef<Path>,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let content = fs::read_to_string(path)?;
-        let user_settings = parse_json_with_comments(&content)?;
-        self.global_settings = Rc::new(merge_settings(&self.default_settings, &user_settings)?);
+    /// Errors from the last attempt to load the user and project settings files.
+    ///
+    /// While a file has an error, the settings from its last successful load stay in effect.
+    pub fn errors(&self) -> impl Iterator<Item = &SettingsError> {
+        self.user_settings_error
+            .iter()
+            .chain(self.project_settings_error.iter())
+    }
+
+    pub fn load_user_settings(&mut self, path: impl AsRef<Path>) -> Result<(), SettingsError> {
+        self.user_settings_path = Some(path.as_ref().to_path_buf());
+        let result = self.try_load_user_settings(path.as_ref());
+        self.user_settings_error = result.as_ref().err().cloned();
+        result
+    }
+
+    fn try_load_user_settings(&mut self, path: &Path) -> Result<(), SettingsError> {
+        let user_settings = read_settings_file(path)?;
+        let global_settings = self
+            .merged_settings(Some(&user_settings))
+            .map_err(|error| SettingsError::json(path, error))?;
+        self.global_settings = Rc::new(global_settings);
         self.user_settings = Some(user_settings);
         Ok(())
     }
 
+    /// Loads a project-local settings file, applied on top of the user settings.
+    pub fn load_project_settings(&mut self, path: impl AsRef<Path>) -> Result<(), SettingsError> {
+        let path = path.as_ref();
+        let result = read_settings_file(path).and_then(|project_settings| {
+            let previous = self.project_settings.replace(project_settings);
+            match self.merged_settings(self.user_settings.as_ref()) {
+                Ok(global_settings) => {
+                    self.global_settings = Rc::new(global_settings);
+                    Ok(())
+                }
+                Err(error) => {
+                    self.project_settings = previous;
+                    Err(SettingsError::json(path, error))
+                }
+            }
+        });
+        self.project_settings_error = result.as_ref().err().cloned();
+        result
+    }
+
+    /// Changes the user settings and writes the changed keys back to the user settings file,
+    /// keeping the rest of the file as it is.
+    ///
+    /// If the changed settings can't be applied, the previous ones stay in effect. Without a
+    /// settings file to write to, the change only lasts until the settings are reloaded.
     pub fn update_user_settings(
         &mut self,
-        _cx: &mut App,
+        cx: &mut App,
         update: impl FnOnce(&mut SettingsContent),
-    ) {
-        // let settings = self
-        //     .user_settings
-        //     .get_or_insert_with(|| self.default_settings.as_ref().clone());
-        if let Some(settings) = self.user_settings.as_mut() {
-            update(settings);
-            self.global_settings = Rc::new(
-                merge_settings(&self.default_settings, settings).expect("failed to merge settings"),
-            );
+    ) -> Result<(), SettingsError> {
+        let path = self
+            .user_settings_path
+            .clone()
+            .or_else(paths::settings_file);
+        let previous = self.user_settings.clone();
+        let settings = self
+            .user_settings
+            .get_or_insert_with(SettingsContent::default);
+        let old_value = serde_json::to_value(&*settings);
+        update(settings);
+        let new_value = serde_json::to_value(&*settings);
+        match self.merged_settings(self.user_settings.as_ref()) {
+            Ok(global_settings) => self.global_settings = Rc::new(global_settings),
+            Err(error) => {
+                self.user_settings = previous;
+                let path = path.unwrap_or_else(|| PathBuf::from("settings.json"));
+                let error = SettingsError::json(&path, error);
+                self.user_settings_error = Some(error.clone());
+                return Err(error);
+            }
         }
+
+        let Some(path) = path else {
+            log::warn!("not saving the settings, there is no configuration directory");
+            return Ok(());
+        };
+        let (Ok(old_value), Ok(new_value)) = (old_value, new_value) else {
+            log::error!("failed to serialize user settings");
+            return Ok(());
+        };
+        let writes = self.user_settings_writes.get_or_insert_with(|| {
+            let (tx, rx) = async_channel::unbounded::<UserSettingsWrite>();
+            cx.background_spawn(async move {
+                while let Ok(write) = rx.recv().await {
+                    let path = &write.path;
+                    if let Err(error) = settings_json::update_settings_file(
+                        path,
+                        &write.old_value,
+                        &write.new_value,
+                    ) {
+                        log::error!("failed to write {}: {error}", path.display());
+                    }
+                }
+            })
+            .detach();
+            tx
+        });
+        writes
+            .try_send(UserSettingsWrite {
+                path,
+                old_value,
+                new_value,
+            })
+            .ok();
+        Ok(())
+    }
+
+    fn merged_settings(
+        &self,
+        user_settings: Option<&SettingsContent>,
+    ) -> serde_json::Result<SettingsContent> {
+        let mut settings = match user_settings {
+            Some(user_settings) => merge_settings(&self.default_settings, user_settings)?,
+            None => self.default_settings.as_ref().clone(),
+        };
+        settings.project = self.project_settings.clone().unwrap_or_default();
+        Ok(settings)
     }
 }
+
+/// A change to the user settings, written back to their file by `update_user_settings`.
+struct UserSettingsWrite {
+    path: PathBuf,
+    old_value: Value,
+    new_value: Value,
+}
+
 pub trait Settings: 'static + Send + Sync + Sized {
     fn from_settings(content: &SettingsContent) -> Self;
     fn get_global(cx: &App) -> Self {
@@ -110,6 +241,39 @@ pub fn default_settings() -> Cow<'static, str> {
     asset_str::<SettingsAssets>("settings/default.json")
 }
 
+/// Watches the file at `path`, sending a message whenever it is created, written or replaced.
+///
+/// The parent directory is watched rather than the file itself, so editors that save by renaming
+/// a temporary file over it keep being picked up. Events stop once the watcher is dropped.
+pub fn watch_config_file(
+    path: PathBuf,
+) -> notify::Result<(RecommendedWatcher, async_channel::Receiver<()>)> {
+    let (tx, rx) = async_channel::unbounded();
+    let file_name = path.file_name().map(ToOwned::to_owned);
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event
+            && !event.kind.is_access()
+            && event
+                .paths
+                .iter()
+                .any(|changed| changed.file_name() == file_name.as_deref())
+        {
+            tx.send_blocking(()).ok();
+        }
+    })?;
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    watcher.watch(directory, RecursiveMode::NonRecursive)?;
+    Ok((watcher, rx))
+}
+
+fn read_settings_file<T: DeserializeOwned>(path: &Path) -> Result<T, SettingsError> {
+    let content = fs::read_to_string(path).map_err(|error| SettingsError::io(path, error))?;
+    parse_json_with_comments(&content).map_err(|error| SettingsError::json(path, error))
+}
+
 pub fn parse_json_with_comments<T: DeserializeOwned>(content: &str) -> serde_json::Result<T> {
     serde_json::from_str(&strip_json_comments(content))
 }
@@ -118,9 +282,17 @@ fn merge_settings(
     default_settings: &SettingsContent,
     user_settings: &SettingsContent,
 ) -> serde_json::Result<SettingsContent> {
-    let mut settings = serde_json::to_value(default_settings)?;
-    merge_json(&mut settings, serde_json::to_value(user_settings)?);
-    serde_json::from_value(settings)
+    merge_content(default_settings, user_settings)
+}
+
+/// Overlays every value set in `overrides` onto `base`.
+pub(crate) fn merge_content<T: Serialize + DeserializeOwned>(
+    base: &T,
+    overrides: &T,
+) -> serde_json::Result<T> {
+    let mut content = serde_json::to_value(base)?;
+    merge_json(&mut content, serde_json::to_value(overrides)?);
+    serde_json::from_value(content)
 }
 
 fn merge_json(default: &mut Value, user: Value) {
@@ -230,4 +402,133 @@ mod tests {
 
         fs::remove_file(path).unwrap();
     }
+
+    #[test]
+    fn test_project_settings_override_terminal_subset() {
+        use std::fs;
+
+        let dir = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("project_settings_test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        fs::write(
+            &path,
+            r#"{
+                "terminal": {
+                    "env": { "PROJECT": "1" },
+                    "path_hyperlink_timeout_ms": 7,
+                },
+            }"#,
+        )
+        .unwrap();
+
+        let mut store = SettingsStore::new().unwrap();
+        store.load_project_settings(&path).unwrap();
+
+        let project = store.global_settings().project.terminal.as_ref().unwrap();
+        assert_eq!(project.path_hyperlink_timeout_ms, Some(7));
+        assert_eq!(project.shell, None);
+
+        let default = store.default_settings().terminal.clone().unwrap().project;
+        let mut terminal = default.clone();
+        terminal.merge_from_option(Some(project));
+        assert_eq!(terminal.path_hyperlink_timeout_ms, Some(7));
+        assert_eq!(terminal.shell, default.shell);
+        assert_eq!(
+            terminal.env.unwrap().get("PROJECT").map(String::as_str),
+            Some("1")
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_user_settings_keep_last_good_settings() {
+        use std::fs;
+
+        let dir = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("invalid_settings_test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+
+        let mut store = SettingsStore::new().unwrap();
+        fs::write(&path, r#"{ "vim_mode": true }"#).unwrap();
+        store.load_user_settings(&path).unwrap();
+        assert_eq!(store.errors().count(), 0);
+
+        fs::write(
+            &path,
+            r#"{
+                "vim_mode": false,
+                "proxy": 42,
+            }"#,
+        )
+        .unwrap();
+        let error = store.load_user_settings(&path).unwrap_err();
+        assert_eq!(error.position.map(|(line, _)| line), Some(3));
+        assert!(!error.message.contains("at line"));
+        assert_eq!(store.errors().collect::<Vec<_>>(), [&error]);
+        assert_eq!(store.global_settings().vim_mode, Some(true));
+
+        fs::write(&path, r#"{ "vim_mode": false }"#).unwrap();
+        store.load_user_settings(&path).unwrap();
+        assert_eq!(store.errors().count(), 0);
+        assert_eq!(store.global_settings().vim_mode, Some(false));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[gpui::test]
+    fn test_update_user_settings_keeps_comments_and_order(cx: &mut gpui::TestAppContext) {
+        use std::fs;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(
+            &path,
+            r#"{
+  // Keep me
+  "vim_mode": true,
+  "theme": "One Dark", /* and me */
+  "proxy": "http://127.0.0.1:10809",
+}
+"#,
+        )
+        .unwrap();
+
+        cx.update(|cx| {
+            let mut store = SettingsStore::new().unwrap();
+            store.load_user_settings(&path).unwrap();
+            store
+                .update_user_settings(cx, |settings| {
+                    settings.theme.theme =
+                        Some(ThemeSelection::Static(ThemeName("Ayu Light".into())));
+                    settings.theme.buffer_font_size = Some(FontSize(16.));
+                })
+                .unwrap();
+            assert_eq!(
+                store.global_settings().theme.buffer_font_size,
+                Some(FontSize(16.))
+            );
+            cx.set_global(store);
+        });
+        cx.run_until_parked();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("// Keep me"), "{content}");
+        assert!(content.contains("/* and me */"), "{content}");
+        let vim_mode = content.find("\"vim_mode\"").unwrap();
+        let theme = content.find("\"theme\": \"Ayu Light\"").unwrap();
+        let proxy = content.find("\"proxy\"").unwrap();
+        assert!(vim_mode < theme && theme < proxy, "{content}");
+        let settings = parse_json_with_comments::<SettingsContent>(&content).unwrap();
+        assert_eq!(settings.vim_mode, Some(true));
+        assert_eq!(settings.theme.buffer_font_size, Some(FontSize(16.)));
+    }
 }
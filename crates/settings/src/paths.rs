@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+
+/// The name of the directory holding project-local settings.
+pub const LOCAL_SETTINGS_DIR: &str = ".alacrterm";
+
+/// Returns the directory alacrterm's configuration lives in.
+///
+/// This is `$XDG_CONFIG_HOME/alacrterm`, falling back to `~/.config/alacrterm` on Unix and to the
+/// roaming application data directory on Windows. Without a home directory there is none.
+pub fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            if cfg!(windows) {
+                dirs::config_dir()
+            } else {
+                dirs::home_dir().map(|home| home.join(".config"))
+            }
+        })
+        .map(|base| base.join("alacrterm"))
+}
+
+/// Returns the directory alacrterm keeps its state, such as logs, in.
+///
+/// This is `$XDG_STATE_HOME/alacrterm`, falling back to `~/.local/state/alacrterm` on Unix and to
+/// the local application data directory on Windows. Without a home directory there is none.
+pub fn state_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            if cfg!(windows) {
+                dirs::data_local_dir()
+            } else {
+                dirs::home_dir().map(|home| home.join(".local").join("state"))
+            }
+        })
+        .map(|base| base.join("alacrterm"))
+}
+
+/// Returns the path of the log file.
+pub fn log_file() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("alacrterm.log"))
+}
+
+/// Returns the path to the user's `settings.json`.
+pub fn settings_file() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("settings.json"))
+}
+
+/// Returns the path to the user's `keymap.json`.
+pub fn keymap_file() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("keymap.json"))
+}
+
+/// Returns the path of the project-local settings file, relative to a project directory.
+pub fn local_settings_file_relative_path() -> PathBuf {
+    Path::new(LOCAL_SETTINGS_DIR).join("settings.json")
+}
+
+/// Finds the closest project-local settings file in `directory` or any of its ancestors.
+pub fn find_local_settings_file(directory: &Path) -> Option<PathBuf> {
+    let relative_path = local_settings_file_relative_path();
+    directory
+        .ancestors()
+        .map(|ancestor| ancestor.join(&relative_path))
+        .find(|path| path.is_file())
+}
@@ -4,7 +4,7 @@ use gpui::WindowButtonLayout;
 use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
 use serde::{Deserialize, Serialize};
 
-use crate::{TerminalSettingsContent, ThemeSettingsContent};
+use crate::{ProjectTerminalSettingsContent, TerminalSettingsContent, ThemeSettingsContent};
 
 /// The state of the modifier keys at some point in time.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -142,6 +142,27 @@ pub struct StatusBarSettingsContent {
     ///
     /// Default: false
     pub line_endings_button: Option<bool>,
+    /// Whether to show the working directory of the active terminal in the status bar.
+    ///
+    /// Default: true
+    pub show_working_directory: Option<bool>,
+    /// Whether to show the foreground process of the active terminal in the status bar.
+    ///
+    /// Default: true
+    pub show_process: Option<bool>,
+    /// Whether to show the size of the active terminal, in columns and rows, in the status bar.
+    ///
+    /// Default: true
+    pub show_terminal_size: Option<bool>,
+    /// Whether to show when the active terminal is in vi mode or on the alternate screen in the
+    /// status bar.
+    ///
+    /// Default: true
+    pub show_modes: Option<bool>,
+    /// Whether to show how far the active terminal is scrolled back in the status bar.
+    ///
+    /// Default: true
+    pub show_scroll_position: Option<bool>,
 }
 
 #[derive(Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Debug)]
@@ -166,22 +187,42 @@ pub struct TitleBarSettingsContent {
     pub button_layout: Option<WindowButtonLayoutContent>,
 }
 
+/// A base set of key bindings, named after the editor they come from.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub enum BaseKeymapContent {
+    /// Only the default keymap, which follows VSCode.
+    #[default]
+    VSCode,
+    JetBrains,
+    SublimeText,
+    Atom,
+    TextMate,
+    Emacs,
+    Cursor,
+    /// No key bindings besides the user keymap.
+    None,
+}
+
 #[derive(Debug, PartialEq, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SettingsContent {
     #[serde(flatten)]
     pub theme: Box<ThemeSettingsContent>,
 
-    #[serde(flatten)]
     // pub extension: ExtensionSettingsContent,
     pub status_bar: Option<StatusBarSettingsContent>,
 
     /// Configuration of audio in Zed.
     // pub audio: Option<AudioSettingsContent>,
 
+    /// The base set of key bindings to use, on top of the default keymap.
+    ///
+    /// Default: VSCode
+    pub base_keymap: Option<BaseKeymapContent>,
+
     /// A map of log scopes to the desired log level.
     /// Useful for filtering out noisy logs or enabling more verbose logging.
     ///
-    /// Example: {"log": {"client": "warn"}}
+    /// Example: {"log": {"terminal": "debug", "gpui": "warn"}}
     pub log: Option<HashMap<String, String>>,
 
     pub proxy: Option<String>,
@@ -198,4 +239,15 @@ pub struct SettingsContent {
     ///
     /// Default: false
     pub vim_mode: Option<bool>,
+
+    /// Settings from the project-local `.alacrterm/settings.json`, applied on top of the user's.
+    #[serde(skip)]
+    pub project: ProjectSettingsContent,
+}
+
+/// The subset of settings a project-local settings file may override.
+#[derive(Debug, PartialEq, Default, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectSettingsContent {
+    /// Project-specific terminal settings.
+    pub terminal: Option<ProjectTerminalSettingsContent>,
 }
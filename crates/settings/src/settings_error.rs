@@ -0,0 +1,51 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// A settings file that could not be read or parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SettingsError {
+    /// The file the error was found in.
+    pub path: PathBuf,
+    /// One-based line and column of the error, if it was found while parsing.
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl SettingsError {
+    pub fn io(path: &Path, error: std::io::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            position: None,
+            message: error.to_string(),
+        }
+    }
+
+    pub fn json(path: &Path, error: serde_json::Error) -> Self {
+        let message = error.to_string();
+        // serde_json appends the position to its message; it is kept separately instead.
+        let suffix = format!(" at line {} column {}", error.line(), error.column());
+        let message = message
+            .strip_suffix(&suffix)
+            .map(ToOwned::to_owned)
+            .unwrap_or(message);
+        Self {
+            path: path.to_path_buf(),
+            position: (error.line() > 0).then(|| (error.line(), error.column())),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{line}:{column}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for SettingsError {}
@@ -0,0 +1,421 @@
+use std::{fs, io, ops::Range, path::Path};
+
+use serde_json::Value;
+
+/// Rewrites the JSONC `text` that held `old` so that it holds `new`, touching only the keys whose
+/// values changed.
+///
+/// Comments, key order and formatting are kept for everything else. `text` may contain comments
+/// and trailing commas, like the files read by `parse_json_with_comments`. Returns `None` if
+/// `text` is not an object.
+pub(crate) fn update_json_text(text: &str, old: &Value, new: &Value) -> Option<String> {
+    let old = without_nulls(old).unwrap_or(Value::Null);
+    let new = without_nulls(new).unwrap_or(Value::Null);
+    let mut changes = Vec::new();
+    collect_changes(&mut Vec::new(), &old, &new, &mut changes);
+
+    let mut text = if text.trim().is_empty() {
+        "{\n}\n".to_string()
+    } else {
+        text.to_string()
+    };
+    for (path, value) in changes {
+        text = set_value_at_path(&text, &path, &value)?;
+    }
+    Some(text)
+}
+
+/// Applies the difference between `old` and `new` to the settings file at `path`, creating it if
+/// it does not exist yet.
+pub(crate) fn update_settings_file(path: &Path, old: &Value, new: &Value) -> io::Result<()> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error),
+    };
+    let updated = update_json_text(&text, old, new).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} does not contain a JSON object", path.display()),
+        )
+    })?;
+    if updated != text {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, updated)?;
+    }
+    Ok(())
+}
+
+/// Drops `null` members, which stand for unset settings. Objects that only held unset settings
+/// are dropped as well.
+fn without_nulls(value: &Value) -> Option<Value> {
+    match value {
+        Value::Null => None,
+        Value::Object(object) => {
+            let stripped: serde_json::Map<String, Value> = object
+                .iter()
+                .filter_map(|(key, value)| Some((key.clone(), without_nulls(value)?)))
+                .collect();
+            (object.is_empty() || !stripped.is_empty()).then_some(Value::Object(stripped))
+        }
+        value => Some(value.clone()),
+    }
+}
+
+/// Collects the paths whose values differ between `old` and `new`, with `Value::Null` marking
+/// removed keys.
+fn collect_changes(
+    path: &mut Vec<String>,
+    old: &Value,
+    new: &Value,
+    changes: &mut Vec<(Vec<String>, Value)>,
+) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, new_value) in new {
+                path.push(key.clone());
+                collect_changes(
+                    path,
+                    old.get(key).unwrap_or(&Value::Null),
+                    new_value,
+                    changes,
+                );
+                path.pop();
+            }
+            for key in old.keys().filter(|key| !new.contains_key(*key)) {
+                path.push(key.clone());
+                changes.push((path.clone(), Value::Null));
+                path.pop();
+            }
+        }
+        (old, new) if old == new => {}
+        (_, new) => changes.push((path.clone(), new.clone())),
+    }
+}
+
+struct ObjectSpan {
+    /// From the opening to just past the closing brace.
+    range: Range<usize>,
+    entries: Vec<EntrySpan>,
+}
+
+#[derive(Clone)]
+struct EntrySpan {
+    key: String,
+    /// From the key's opening quote to the end of the value.
+    range: Range<usize>,
+    value: Range<usize>,
+    /// The offset of the comma following the value, if any.
+    comma: Option<usize>,
+}
+
+fn set_value_at_path(text: &str, path: &[String], value: &Value) -> Option<String> {
+    let mut object = parse_object(text, skip_whitespace_and_comments(text, 0))?;
+    for (depth, key) in path.iter().enumerate() {
+        let Some(entry) = object
+            .entries
+            .iter()
+            .find(|entry| entry.key == *key)
+            .cloned()
+        else {
+            if value.is_null() {
+                return Some(text.to_string());
+            }
+            let value = nest(&path[depth + 1..], value);
+            return Some(insert_entry(text, &object, key, &value));
+        };
+
+        if depth + 1 == path.len() {
+            return Some(if value.is_null() {
+                remove_entry(text, &entry)
+            } else {
+                let indent = line_indent(text, entry.range.start);
+                replace(text, entry.value, &format_value(value, indent))
+            });
+        }
+
+        match parse_object(text, entry.value.start) {
+            Some(inner) => object = inner,
+            None if value.is_null() => return Some(text.to_string()),
+            None => {
+                let indent = line_indent(text, entry.range.start);
+                let value = nest(&path[depth + 1..], value);
+                return Some(replace(text, entry.value, &format_value(&value, indent)));
+            }
+        }
+    }
+    None
+}
+
+/// Wraps `value` in one object per key of `path`.
+fn nest(path: &[String], value: &Value) -> Value {
+    path.iter().rev().fold(value.clone(), |value, key| {
+        let mut object = serde_json::Map::new();
+        object.insert(key.clone(), value);
+        Value::Object(object)
+    })
+}
+
+fn insert_entry(text: &str, object: &ObjectSpan, key: &str, value: &Value) -> String {
+    let close = object.range.end - 1;
+    let Some(last) = object.entries.last() else {
+        let outer_indent = line_indent(text, object.range.start);
+        let indent = format!("{outer_indent}  ");
+        let entry = format_entry(key, value, &indent);
+        let inserted = format!("\n{indent}{entry}\n{outer_indent}");
+        return replace(text, object.range.start + 1..close, &inserted);
+    };
+
+    let indent = line_indent(text, last.range.start);
+    let mut entry = format_entry(key, value, indent);
+    // Follow the file's style of ending the last entry with a comma or not.
+    if last.comma.is_some() {
+        entry.push(',');
+    }
+
+    // Insert before the closing brace, after any comments trailing the last entry.
+    let line_start = line_start(text, close);
+    let mut text = if text[line_start..close].trim().is_empty() {
+        replace(text, line_start..line_start, &format!("{indent}{entry}\n"))
+    } else {
+        replace(text, close..close, &format!("\n{indent}{entry}\n"))
+    };
+    if last.comma.is_none() {
+        text.insert(last.value.end, ',');
+    }
+    text
+}
+
+fn remove_entry(text: &str, entry: &EntrySpan) -> String {
+    let mut start = entry.range.start;
+    let mut end = entry.comma.map_or(entry.range.end, |comma| comma + 1);
+
+    // Take the whole line along if the entry is the only thing on it.
+    let line_start = line_start(text, start);
+    let line_end = text[end..].find('\n').map_or(text.len(), |ix| end + ix + 1);
+    if text[line_start..start].trim().is_empty() && text[end..line_end].trim().is_empty() {
+        start = line_start;
+        end = line_end;
+    }
+    replace(text, start..end, "")
+}
+
+fn format_entry(key: &str, value: &Value, indent: &str) -> String {
+    format!("{}: {}", Value::from(key), format_value(value, indent))
+}
+
+/// Pretty-prints `value` for a key indented by `indent`.
+fn format_value(value: &Value, indent: &str) -> String {
+    serde_json::to_string_pretty(value)
+        .unwrap_or_else(|_| value.to_string())
+        .replace('\n', &format!("\n{indent}"))
+}
+
+fn replace(text: &str, range: Range<usize>, replacement: &str) -> String {
+    let mut result = String::with_capacity(text.len() + replacement.len());
+    result.push_str(&text[..range.start]);
+    result.push_str(replacement);
+    result.push_str(&text[range.end..]);
+    result
+}
+
+fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind('\n').map_or(0, |ix| ix + 1)
+}
+
+fn line_indent(text: &str, offset: usize) -> &str {
+    let line = &text[line_start(text, offset)..offset];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Returns the byte offset of the first character at or after `offset` that is neither
+/// whitespace nor part of a comment.
+fn skip_whitespace_and_comments(content: &str, mut offset: usize) -> usize {
+    loop {
+        let rest = &content[offset..];
+        let trimmed = rest.trim_start();
+        offset += rest.len() - trimmed.len();
+        if trimmed.starts_with("//") {
+            offset += trimmed.find('\n').unwrap_or(trimmed.len());
+        } else if let Some(comment) = trimmed.strip_prefix("/*") {
+            offset += comment.find("*/").map_or(trimmed.len(), |end| end + 4);
+        } else {
+            return offset;
+        }
+    }
+}
+
+fn parse_object(text: &str, start: usize) -> Option<ObjectSpan> {
+    if !text[start..].starts_with('{') {
+        return None;
+    }
+    let mut entries = Vec::new();
+    let mut offset = start + 1;
+    loop {
+        offset = skip_whitespace_and_comments(text, offset);
+        if text[offset..].starts_with('}') {
+            return Some(ObjectSpan {
+                range: start..offset + 1,
+                entries,
+            });
+        }
+
+        let key_start = offset;
+        let (key, key_end) = parse_string(text, key_start)?;
+        offset = skip_whitespace_and_comments(text, key_end);
+        if !text[offset..].starts_with(':') {
+            return None;
+        }
+        let value_start = skip_whitespace_and_comments(text, offset + 1);
+        let value_end = parse_value(text, value_start)?;
+        offset = skip_whitespace_and_comments(text, value_end);
+        let comma = text[offset..].starts_with(',').then_some(offset);
+        match comma {
+            Some(comma) => offset = comma + 1,
+            None if text[offset..].starts_with('}') => {}
+            None => return None,
+        }
+        entries.push(EntrySpan {
+            key,
+            range: key_start..value_end,
+            value: value_start..value_end,
+            comma,
+        });
+    }
+}
+
+fn parse_array(text: &str, start: usize) -> Option<usize> {
+    let mut offset = start + 1;
+    loop {
+        offset = skip_whitespace_and_comments(text, offset);
+        if text[offset..].starts_with(']') {
+            return Some(offset + 1);
+        }
+        offset = skip_whitespace_and_comments(text, parse_value(text, offset)?);
+        if text[offset..].starts_with(',') {
+            offset += 1;
+        } else if !text[offset..].starts_with(']') {
+            return None;
+        }
+    }
+}
+
+/// Parses the string starting at `start`, returning its contents and the offset past it.
+fn parse_string(text: &str, start: usize) -> Option<(String, usize)> {
+    let bytes = text.as_bytes();
+    if bytes.get(start) != Some(&b'"') {
+        return None;
+    }
+    let mut offset = start + 1;
+    while offset < bytes.len() {
+        match bytes[offset] {
+            b'\\' => offset += 2,
+            b'"' => {
+                let end = offset + 1;
+                return Some((serde_json::from_str(&text[start..end]).ok()?, end));
+            }
+            _ => offset += 1,
+        }
+    }
+    None
+}
+
+/// Returns the offset just past the value starting at `start`.
+fn parse_value(text: &str, start: usize) -> Option<usize> {
+    match text.as_bytes().get(start)? {
+        b'{' => parse_object(text, start).map(|object| object.range.end),
+        b'[' => parse_array(text, start),
+        b'"' => parse_string(text, start).map(|(_, end)| end),
+        _ => {
+            let end = text[start..]
+                .find(|ch: char| ch.is_whitespace() || matches!(ch, ',' | '}' | ']' | '/'))
+                .map_or(text.len(), |len| start + len);
+            (end > start).then_some(end)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::parse_json_with_comments;
+
+    #[test]
+    fn test_update_json_text_keeps_comments_and_order() {
+        let text = r#"{
+  // Keep this comment.
+  "vim_mode": false,
+  /* and this one */
+  "terminal": {
+    "font_size": 13, // the font size
+    "blinking": "off",
+  },
+}
+"#;
+        let old = parse_json_with_comments::<Value>(text).unwrap();
+        let mut new = old.clone();
+        new["vim_mode"] = json!(true);
+        new["terminal"]["font_size"] = json!(15);
+
+        let updated = update_json_text(text, &old, &new).unwrap();
+        assert_eq!(
+            updated,
+            r#"{
+  // Keep this comment.
+  "vim_mode": true,
+  /* and this one */
+  "terminal": {
+    "font_size": 15, // the font size
+    "blinking": "off",
+  },
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_update_json_text_inserts_nested_keys() {
+        let text = "{\n  \"vim_mode\": true // vim\n}\n";
+        let old = json!({ "vim_mode": true, "terminal": null });
+        let new = json!({
+            "vim_mode": true,
+            "terminal": { "font_size": 15, "blinking": null },
+        });
+
+        let updated = update_json_text(text, &old, &new).unwrap();
+        assert_eq!(
+            updated,
+            "{\n  \"vim_mode\": true, // vim\n  \"terminal\": {\n    \"font_size\": 15\n  }\n}\n"
+        );
+        assert_eq!(
+            parse_json_with_comments::<Value>(&updated).unwrap(),
+            json!({ "vim_mode": true, "terminal": { "font_size": 15 } })
+        );
+    }
+
+    #[test]
+    fn test_update_json_text_removes_unset_keys() {
+        let text = "{\n  // Vim.\n  \"vim_mode\": true,\n  \"proxy\": \"http://localhost\",\n}\n";
+        let old = json!({ "vim_mode": true, "proxy": "http://localhost" });
+        let new = json!({ "vim_mode": true, "proxy": null });
+
+        let updated = update_json_text(text, &old, &new).unwrap();
+        assert_eq!(updated, "{\n  // Vim.\n  \"vim_mode\": true,\n}\n");
+    }
+
+    #[test]
+    fn test_update_json_text_empty_and_invalid_files() {
+        let new = json!({ "vim_mode": true });
+        let created = update_json_text("", &json!({}), &new).unwrap();
+        assert_eq!(parse_json_with_comments::<Value>(&created).unwrap(), new);
+
+        let updated = update_json_text("{}", &json!({}), &new).unwrap();
+        assert_eq!(parse_json_with_comments::<Value>(&updated).unwrap(), new);
+
+        assert_eq!(update_json_text("[1, 2]", &json!({}), &new), None);
+    }
+}
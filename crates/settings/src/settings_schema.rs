@@ -0,0 +1,116 @@
+use std::{fs, io, path::Path};
+
+use serde_json::{Value, json};
+
+use crate::{SettingsContent, parse_json_with_comments, settings_json};
+
+/// The file name of the schema written next to the user settings.
+pub const SETTINGS_SCHEMA_FILE_NAME: &str = "settings.schema.json";
+
+/// Returns the JSON schema of `settings.json`, used by editors for completion and validation.
+pub fn settings_schema() -> Value {
+    let mut schema = schemars::schema_for!(SettingsContent).to_value();
+    // Editors look up the schema through this key, so it has to be a valid one.
+    if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+        properties.insert(
+            "$schema".to_string(),
+            json!({
+                "type": "string",
+                "description": "The JSON schema this file is validated against."
+            }),
+        );
+    }
+    schema
+}
+
+/// Writes the settings schema next to `settings_path` and points the settings file at it with a
+/// `$schema` key, creating the file if needed. The rest of an existing file is kept as it is.
+pub fn install_settings_schema(settings_path: &Path) -> io::Result<()> {
+    let schema_path = settings_path.with_file_name(SETTINGS_SCHEMA_FILE_NAME);
+    if let Some(directory) = schema_path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    let schema = serde_json::to_string_pretty(&settings_schema())?;
+    // Skip identical writes so the schema's modification time only changes with the schema.
+    if fs::read_to_string(&schema_path).ok().as_deref() != Some(schema.as_str()) {
+        fs::write(&schema_path, schema)?;
+    }
+
+    let settings = match fs::read_to_string(settings_path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error),
+    };
+    let has_schema = if settings.trim().is_empty() {
+        false
+    } else {
+        // Files that fail to parse are reported when they are loaded, leave them to the user.
+        match parse_json_with_comments::<Value>(&settings) {
+            Ok(Value::Object(settings)) => settings.contains_key("$schema"),
+            _ => true,
+        }
+    };
+    if !has_schema {
+        let schema = format!("./{SETTINGS_SCHEMA_FILE_NAME}");
+        settings_json::update_settings_file(
+            settings_path,
+            &json!({}),
+            &json!({ "$schema": schema }),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_schema_includes_custom_schemas() {
+        let schema = settings_schema();
+        let schema_text = schema.to_string();
+
+        assert!(schema["properties"]["$schema"].is_object());
+        assert!(schema["properties"]["terminal"].is_object());
+        // FontFeaturesContent
+        assert!(schema_text.contains("[0-9a-zA-Z]{4}$"));
+        // WindowButtonLayoutContent
+        assert!(schema_text.contains("platform_default"));
+    }
+
+    #[test]
+    fn test_install_settings_schema() {
+        let directory = tempfile::tempdir().unwrap();
+        let settings_path = directory.path().join("settings.json");
+
+        install_settings_schema(&settings_path).unwrap();
+        assert!(directory.path().join(SETTINGS_SCHEMA_FILE_NAME).exists());
+        let settings = fs::read_to_string(&settings_path).unwrap();
+        assert_eq!(
+            settings,
+            "{\n  \"$schema\": \"./settings.schema.json\"\n}\n"
+        );
+        parse_json_with_comments::<SettingsContent>(&settings).unwrap();
+
+        // Existing files keep their comments, and get the key only once.
+        fs::write(
+            &settings_path,
+            "{\n  // Larger\n  \"ui_font_size\": 18\n}\n",
+        )
+        .unwrap();
+        install_settings_schema(&settings_path).unwrap();
+        install_settings_schema(&settings_path).unwrap();
+        assert_eq!(
+            fs::read_to_string(&settings_path).unwrap(),
+            "{\n  // Larger\n  \"ui_font_size\": 18,\n  \"$schema\": \"./settings.schema.json\"\n}\n"
+        );
+
+        // Files that don't parse are left alone.
+        fs::write(&settings_path, "{ \"ui_font_size\": }").unwrap();
+        install_settings_schema(&settings_path).unwrap();
+        assert_eq!(
+            fs::read_to_string(&settings_path).unwrap(),
+            "{ \"ui_font_size\": }"
+        );
+    }
+}
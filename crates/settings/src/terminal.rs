@@ -3,7 +3,11 @@ use std::{collections::HashMap, path::PathBuf};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::{FontFamilyName, FontFeaturesContent, FontSize, FontWeightContent, ShowScrollbar};
+use util::ResultExt as _;
+
+use crate::{
+    FontFamilyName, FontFeaturesContent, FontSize, FontWeightContent, ShowScrollbar, merge_content,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(untagged)]
@@ -34,6 +38,32 @@ pub enum TerminalBell {
     Off,
 }
 
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    strum::VariantArray,
+    strum::VariantNames,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalExitBehavior {
+    /// Close the pane the terminal was running in.
+    #[default]
+    ClosePane,
+    /// Close the whole tab containing the terminal.
+    CloseTab,
+    /// Close the window containing the terminal.
+    CloseWindow,
+    /// Keep the final screen and offer to restart the terminal.
+    Hold,
+}
+
 #[derive(
     Copy,
     Clone,
@@ -317,6 +347,17 @@ pub struct ProjectTerminalSettingsContent {
     pub path_hyperlink_timeout_ms: Option<u64>,
 }
 
+impl ProjectTerminalSettingsContent {
+    /// Overrides every field that is set in `other`, leaving the rest untouched.
+    pub fn merge_from_option(&mut self, other: Option<&Self>) {
+        if let Some(other) = other
+            && let Some(merged) = merge_content(self, other).log_err()
+        {
+            *self = merged;
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct TerminalSettingsContent {
     #[serde(flatten)]
@@ -417,4 +458,14 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: "system"
     pub bell: Option<TerminalBell>,
+    /// What to do when the terminal's process exits.
+    /// Can be "close_pane", "close_tab", "close_window", or "hold".
+    ///
+    /// Default: "close_pane"
+    pub on_exit: Option<TerminalExitBehavior>,
+    /// The window title, with `{title}`, `{osc_title}`, `{process}`, `{cwd}`, `{host}`, `{cols}`
+    /// and `{rows}` replaced by those of the active terminal.
+    ///
+    /// Default: "{title}"
+    pub title: Option<String>,
 }
@@ -276,8 +276,8 @@ pub enum ThemeSelection {
     },
 }
 
-pub const DEFAULT_LIGHT_THEME: &'static str = "One Light";
-pub const DEFAULT_DARK_THEME: &'static str = "One Dark";
+pub const DEFAULT_LIGHT_THEME: &'static str = "Ayu Light";
+pub const DEFAULT_DARK_THEME: &'static str = "Tokyo Night";
 
 impl Default for ThemeSelection {
     fn default() -> Self {
@@ -7,7 +7,7 @@ use std::{borrow::Cow, io, ops::RangeInclusive, path::PathBuf, sync::Arc};
 mod hyperlinks;
 
 use alacritty_terminal::{
-    event::{Event as AlacTermEvent, EventListener, Notify, WindowSize},
+    event::{Event as AlacTermEvent, EventListener, Notify, OnResize, WindowSize},
     event_loop::{EventLoop, Msg, Notifier},
     grid::{Dimensions, Grid, GridIterator, Row, Scroll as AlacScroll},
     index::{Boundary, Column, Direction as AlacDirection, Line, Point as AlacPoint},
@@ -21,7 +21,7 @@ use alacritty_terminal::{
         cell::{Cell as AlacCell, Flags, Hyperlink as AlacHyperlink},
         search::{Match, RegexIter, RegexSearch},
     },
-    tty,
+    tty::{self, ChildEvent, EventedPty, EventedReadWrite},
     vi_mode::{ViModeCursor, ViMotion as AlacViMotion},
     vte::ansi::{
         ClearMode, CursorShape as AlacCursorShape, CursorStyle as AlacCursorStyle,
@@ -30,6 +30,7 @@ use alacritty_terminal::{
 };
 use anyhow::{Context as _, Result};
 use futures::channel::mpsc::UnboundedSender;
+use polling::{Event as PollingEvent, PollMode, Poller};
 use util::paths::PathStyle;
 use vte::ansi::Handler;
 #[cfg(target_os = "windows")]
@@ -39,7 +40,9 @@ use crate::{
     Cell, Color, Content, Cursor, CursorShape, Hyperlink, HyperlinkData, IndexedCell, Modes, Point,
     PtyEvent, Range, RenderableCells, Scroll, Search, Selection, SelectionRange, SelectionSide,
     SelectionType, TerminalBackendEvent, TerminalBounds, ViMotion,
+    modify_other_keys::ModifyOtherKeysTracker,
     pty_info::ProcessIdGetter,
+    shell_integration::{ShellIntegrationFilter, ShellMark, ShellRegion},
     terminal_settings::{AlternateScroll, CursorShape as SettingsCursorShape},
 };
 
@@ -81,6 +84,108 @@ impl From<&AlacrittyPty> for ProcessIdGetter {
     }
 }
 
+/// The PTY of a terminal, with its output going through the [`ShellIntegrationFilter`] and the
+/// [`ModifyOtherKeysTracker`] before alacritty parses it.
+pub(super) struct ShellIntegrationPty {
+    pty: AlacrittyPty,
+    filter: ShellIntegrationFilter,
+    modify_other_keys: ModifyOtherKeysTracker,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    events_tx: UnboundedSender<PtyEvent>,
+}
+
+impl ShellIntegrationPty {
+    fn new(pty: AlacrittyPty, events_tx: UnboundedSender<PtyEvent>) -> Self {
+        Self {
+            pty,
+            filter: ShellIntegrationFilter::new(),
+            modify_other_keys: ModifyOtherKeysTracker::new(),
+            input: Vec::new(),
+            output: Vec::new(),
+            events_tx,
+        }
+    }
+}
+
+impl io::Read for ShellIntegrationPty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Read no more than fits `buf` once tagged. The event loop only reads again when the PTY
+        // has more output, so output held back here could go unseen.
+        let len = ShellIntegrationFilter::max_input_len(buf.len());
+        if len == 0 {
+            // Only happens while the event loop has unparsed bytes in `buf`. It calls again with
+            // the whole buffer after parsing them.
+            return Ok(0);
+        }
+        self.input.resize(len, 0);
+        let read = self.pty.reader().read(&mut self.input)?;
+        if let Some(enabled) = self.modify_other_keys.advance(&self.input[..read]) {
+            self.events_tx
+                .unbounded_send(PtyEvent::ModifyOtherKeysChanged(enabled))
+                .ok();
+        }
+        self.output.clear();
+        self.filter.filter(&self.input[..read], &mut self.output);
+        for event in self.filter.take_events() {
+            self.events_tx
+                .unbounded_send(PtyEvent::ShellIntegration(event))
+                .ok();
+        }
+
+        buf[..self.output.len()].copy_from_slice(&self.output);
+        Ok(self.output.len())
+    }
+}
+
+impl EventedReadWrite for ShellIntegrationPty {
+    type Reader = Self;
+    type Writer = <AlacrittyPty as EventedReadWrite>::Writer;
+
+    unsafe fn register(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: PollingEvent,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        // SAFETY: The PTY lives as long as `self`, which the caller keeps registered.
+        unsafe { self.pty.register(poller, interest, mode) }
+    }
+
+    fn reregister(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: PollingEvent,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Arc<Poller>) -> io::Result<()> {
+        self.pty.deregister(poller)
+    }
+
+    fn reader(&mut self) -> &mut Self::Reader {
+        self
+    }
+
+    fn writer(&mut self) -> &mut Self::Writer {
+        self.pty.writer()
+    }
+}
+
+impl EventedPty for ShellIntegrationPty {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        self.pty.next_child_event()
+    }
+}
+
+impl OnResize for ShellIntegrationPty {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.pty.on_resize(window_size);
+    }
+}
+
 pub(super) struct PtySender {
     notifier: Notifier,
 }
@@ -135,6 +240,7 @@ pub(super) fn pty_term_config(
     Config {
         scrolling_history,
         default_cursor_style: alacritty_cursor_style(cursor_shape),
+        kitty_keyboard: true,
         ..Config::default()
     }
 }
@@ -195,6 +301,7 @@ pub(super) fn spawn_event_loop(
     pty: AlacrittyPty,
     drain_on_exit: bool,
 ) -> Result<PtySender> {
+    let pty = ShellIntegrationPty::new(pty, events_tx.clone());
     let event_loop = EventLoop::new(term, ZedListener(events_tx), pty, drain_on_exit, false)
         .context("failed to create event loop")?;
     let pty_tx = event_loop.channel();
@@ -242,6 +349,80 @@ pub(super) fn scroll_to_point(term: &mut AlacrittyTerm, point: Point) {
     term.scroll_to_point(point.to_alacritty());
 }
 
+/// Finds the first shell mark `predicate` accepts on `lines`, in the order they are given.
+pub(super) fn find_shell_mark(
+    term: &AlacrittyTerm,
+    lines: impl IntoIterator<Item = i32>,
+    mut predicate: impl FnMut(ShellMark) -> bool,
+) -> Option<(Point, ShellMark)> {
+    let grid = term.grid();
+    lines.into_iter().find_map(|line| {
+        let row = &grid[Line(line)];
+        (0..grid.columns()).find_map(|column| {
+            let mark = shell_mark(&row[Column(column)])?;
+            predicate(mark).then(|| (Point::new(line, column), mark))
+        })
+    })
+}
+
+/// Returns the shell mark closest at or above `point`'s line, of the command block there.
+pub(super) fn shell_mark_at(term: &AlacrittyTerm, point: Point) -> Option<(Point, ShellMark)> {
+    let lines = (term.topmost_line().0..=point.line).rev();
+    find_shell_mark(term, lines, |_| true)
+}
+
+/// Returns the last shell mark of the command block `block`, scanning up from the bottom of the
+/// grid no further than the marks of the blocks before it.
+pub(super) fn last_shell_mark(term: &AlacrittyTerm, block: u64) -> Option<(Point, ShellMark)> {
+    let lines = (term.topmost_line().0..=term.bottommost_line().0).rev();
+    find_shell_mark(term, lines, |mark| mark.block <= block).filter(|(_, mark)| mark.block == block)
+}
+
+/// Returns the range of the output of the command block of `mark`, found at `point`, from its
+/// first cell up to the next block, or up to the cursor while none followed it. `None` if the
+/// command printed nothing that is still in the grid. Only the lines of the block are scanned.
+pub(super) fn command_output_range(
+    term: &AlacrittyTerm,
+    point: Point,
+    mark: ShellMark,
+) -> Option<Range> {
+    let last_line = term.bottommost_line().0;
+    let block = mark.block;
+    let start = if mark.region == ShellRegion::Output {
+        point
+    } else {
+        // The output follows the prompt and the command, unless the next block does.
+        let (start, _) = find_shell_mark(term, point.line..=last_line, |mark| {
+            mark.block != block || mark.region == ShellRegion::Output
+        })
+        .filter(|(_, mark)| mark.block == block)?;
+        start
+    };
+    let end = find_shell_mark(term, start.line..=last_line, |mark| mark.block > block).map_or_else(
+        || terminal_point_from_alacritty(term.grid().cursor.point),
+        |(point, _)| point,
+    );
+
+    // The output ends at the cell before the end.
+    let end = match end.column.checked_sub(1) {
+        Some(column) => Point::new(end.line, column),
+        None => Point::new(end.line - 1, term.last_column().0),
+    };
+    (end >= start).then(|| Range::new(start, end))
+}
+
+/// Returns the text `selection` would copy, leaving the selection of the terminal alone.
+pub(super) fn text_of_selection(term: &mut AlacrittyTerm, selection: &Selection) -> Option<String> {
+    let previous_selection = term.selection.replace(selection.to_alacritty());
+    let text = selection_text(term);
+    term.selection = previous_selection;
+    text
+}
+
+pub(super) fn vi_cursor_point(term: &AlacrittyTerm) -> Point {
+    terminal_point_from_alacritty(term.vi_mode_cursor.point)
+}
+
 pub(super) fn vi_goto_point(term: &mut AlacrittyTerm, point: Point) {
     term.vi_goto_point(point.to_alacritty());
 }
@@ -429,6 +610,17 @@ impl Hyperlink {
     }
 }
 
+/// Returns the hyperlink of the application on `cell`, leaving out the ones tagging shell marks.
+fn app_hyperlink(cell: &AlacCell) -> Option<AlacHyperlink> {
+    cell.hyperlink()
+        .filter(|hyperlink| ShellMark::from_uri(hyperlink.uri()).is_none())
+}
+
+fn shell_mark(cell: &AlacCell) -> Option<ShellMark> {
+    cell.hyperlink()
+        .and_then(|hyperlink| ShellMark::from_uri(hyperlink.uri()))
+}
+
 fn terminal_hyperlink_from_alacritty(hyperlink: AlacHyperlink) -> Hyperlink {
     Hyperlink::from_alacritty(hyperlink)
 }
@@ -479,7 +671,13 @@ impl Cell {
 
     #[inline]
     pub fn hyperlink(&self) -> Option<Hyperlink> {
-        self.cell.hyperlink().map(terminal_hyperlink_from_alacritty)
+        app_hyperlink(&self.cell).map(terminal_hyperlink_from_alacritty)
+    }
+
+    /// The shell mark anchored at this cell, if it starts a region of a command block.
+    #[inline]
+    pub fn shell_mark(&self) -> Option<ShellMark> {
+        shell_mark(&self.cell)
     }
 
     #[inline]
@@ -592,6 +790,36 @@ impl Modes {
         add_alacritty_mode(&mut mode, self, Self::MOUSE_DRAG, TermMode::MOUSE_DRAG);
         add_alacritty_mode(&mut mode, self, Self::MOUSE_MOTION, TermMode::MOUSE_MOTION);
         add_alacritty_mode(&mut mode, self, Self::VI, TermMode::VI);
+        add_alacritty_mode(
+            &mut mode,
+            self,
+            Self::DISAMBIGUATE_ESC_CODES,
+            TermMode::DISAMBIGUATE_ESC_CODES,
+        );
+        add_alacritty_mode(
+            &mut mode,
+            self,
+            Self::REPORT_EVENT_TYPES,
+            TermMode::REPORT_EVENT_TYPES,
+        );
+        add_alacritty_mode(
+            &mut mode,
+            self,
+            Self::REPORT_ALTERNATE_KEYS,
+            TermMode::REPORT_ALTERNATE_KEYS,
+        );
+        add_alacritty_mode(
+            &mut mode,
+            self,
+            Self::REPORT_ALL_KEYS_AS_ESC,
+            TermMode::REPORT_ALL_KEYS_AS_ESC,
+        );
+        add_alacritty_mode(
+            &mut mode,
+            self,
+            Self::REPORT_ASSOCIATED_TEXT,
+            TermMode::REPORT_ASSOCIATED_TEXT,
+        );
         mode
     }
 }
@@ -685,6 +913,36 @@ fn terminal_modes_from_alacritty(mode: TermMode) -> Modes {
         Modes::MOUSE_MOTION,
     );
     add_terminal_mode(&mut terminal_modes, mode, TermMode::VI, Modes::VI);
+    add_terminal_mode(
+        &mut terminal_modes,
+        mode,
+        TermMode::DISAMBIGUATE_ESC_CODES,
+        Modes::DISAMBIGUATE_ESC_CODES,
+    );
+    add_terminal_mode(
+        &mut terminal_modes,
+        mode,
+        TermMode::REPORT_EVENT_TYPES,
+        Modes::REPORT_EVENT_TYPES,
+    );
+    add_terminal_mode(
+        &mut terminal_modes,
+        mode,
+        TermMode::REPORT_ALTERNATE_KEYS,
+        Modes::REPORT_ALTERNATE_KEYS,
+    );
+    add_terminal_mode(
+        &mut terminal_modes,
+        mode,
+        TermMode::REPORT_ALL_KEYS_AS_ESC,
+        Modes::REPORT_ALL_KEYS_AS_ESC,
+    );
+    add_terminal_mode(
+        &mut terminal_modes,
+        mode,
+        TermMode::REPORT_ASSOCIATED_TEXT,
+        Modes::REPORT_ASSOCIATED_TEXT,
+    );
     terminal_modes
 }
 
@@ -966,8 +1224,53 @@ fn all_search_matches<'a, T>(
 mod tests {
     use std::sync::Arc;
 
+    use vte::ansi::{Processor, StdSyncHandler};
+
     use super::*;
 
+    #[test]
+    fn command_output_range_ends_before_next_block() {
+        let (events_tx, _events_rx) = futures::channel::mpsc::unbounded();
+        let config = display_only_term_config(100, SettingsCursorShape::Block);
+        let term = new_term(
+            &config,
+            TerminalBounds::default(),
+            events_tx,
+            AlternateScroll::On,
+        );
+        let mut term = term.lock();
+        let mut input = Vec::new();
+        ShellIntegrationFilter::new().filter(
+            b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07a\r\nb\r\n\x1b]133;D;0\x07\
+              \x1b]133;A\x07$ \x1b]133;B\x07true\r\n\x1b]133;C\x07\x1b]133;D;0\x07\
+              \x1b]133;A\x07$ \x1b]133;B\x07cat\r\n\x1b]133;C\x07c",
+            &mut input,
+        );
+        Processor::<StdSyncHandler>::new().advance(&mut *term, &input);
+
+        let output = |term: &mut AlacrittyTerm, (point, mark): (Point, ShellMark)| {
+            let range = command_output_range(term, point, mark)?;
+            text_of_selection(term, &Selection::simple_range(range))
+        };
+        let last_mark = |term: &AlacrittyTerm, block| last_shell_mark(term, block).unwrap();
+        let mark = last_mark(&term, 1);
+        assert_eq!(output(&mut term, mark).as_deref(), Some("a\nb"));
+        // A command that printed nothing has no output.
+        let mark = last_mark(&term, 2);
+        assert_eq!(output(&mut term, mark), None);
+        // The output of a running command ends at the cursor.
+        let mark = last_mark(&term, 3);
+        assert_eq!(output(&mut term, mark).as_deref(), Some("c"));
+        assert!(term.selection.is_none());
+
+        let block_at = |point| shell_mark_at(&term, point).map(|(_, mark)| mark.block);
+        assert_eq!(block_at(Point::new(2, 0)), Some(1));
+        assert_eq!(block_at(Point::new(4, 3)), Some(3));
+        // The output is found from the prompt of its block too.
+        let mark = shell_mark_at(&term, Point::new(0, 3)).unwrap();
+        assert_eq!(output(&mut term, mark).as_deref(), Some("a\nb"));
+    }
+
     #[test]
     fn terminal_hyperlink_from_alacritty_keeps_alacritty_storage() {
         let hyperlink = AlacHyperlink::new(Some("id"), "https://example.com".to_string());
@@ -998,7 +1301,9 @@ mod tests {
             | TermMode::ALT_SCREEN
             | TermMode::MOUSE_DRAG
             | TermMode::SGR_MOUSE
-            | TermMode::VI;
+            | TermMode::VI
+            | TermMode::DISAMBIGUATE_ESC_CODES
+            | TermMode::REPORT_EVENT_TYPES;
 
         let terminal_modes = terminal_modes_from_alacritty(alacritty_modes);
         assert!(terminal_modes.contains(Modes::APP_CURSOR));
@@ -1008,6 +1313,9 @@ mod tests {
         assert!(terminal_modes.intersects(Modes::MOUSE_MODE));
         assert!(terminal_modes.contains(Modes::SGR_MOUSE));
         assert!(terminal_modes.contains(Modes::VI));
+        assert!(terminal_modes.contains(Modes::DISAMBIGUATE_ESC_CODES));
+        assert!(terminal_modes.contains(Modes::REPORT_EVENT_TYPES));
+        assert!(!terminal_modes.contains(Modes::REPORT_ALL_KEYS_AS_ESC));
         assert!(!terminal_modes.contains(Modes::MOUSE_REPORT_CLICK));
 
         let alacritty_modes = terminal_modes.to_alacritty();
@@ -1017,6 +1325,9 @@ mod tests {
         assert!(alacritty_modes.contains(TermMode::MOUSE_DRAG));
         assert!(alacritty_modes.contains(TermMode::SGR_MOUSE));
         assert!(alacritty_modes.contains(TermMode::VI));
+        assert!(alacritty_modes.contains(TermMode::DISAMBIGUATE_ESC_CODES));
+        assert!(alacritty_modes.contains(TermMode::REPORT_EVENT_TYPES));
+        assert!(!alacritty_modes.contains(TermMode::REPORT_ALL_KEYS_AS_ESC));
         assert!(!alacritty_modes.contains(TermMode::MOUSE_REPORT_CLICK));
     }
 
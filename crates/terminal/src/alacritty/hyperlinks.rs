@@ -17,6 +17,7 @@ use std::{
 use url::Url;
 use util::paths::{PathStyle, UrlExt};
 
+use super::app_hyperlink;
 use crate::Range;
 
 const URL_REGEX: &str = r#"(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file://|git://|ssh:|ftp://)[^\u{0000}-\u{001F}\u{007F}-\u{009F}<>"\s{-}\^⟨⟩`']+"#;
@@ -94,12 +95,12 @@ pub(crate) fn find_from_grid_point<T: EventListener>(
     path_style: PathStyle,
 ) -> Option<HyperlinkMatch> {
     let grid = term.grid();
-    let link = grid.index(point).hyperlink();
+    let link = app_hyperlink(grid.index(point));
     let found_word = if let Some(ref url) = link {
         let mut min_index = point;
         loop {
             let new_min_index = min_index.sub(term, Boundary::Cursor, 1);
-            if new_min_index == min_index || grid.index(new_min_index).hyperlink() != link {
+            if new_min_index == min_index || app_hyperlink(grid.index(new_min_index)) != link {
                 break;
             } else {
                 min_index = new_min_index
@@ -109,7 +110,7 @@ pub(crate) fn find_from_grid_point<T: EventListener>(
         let mut max_index = point;
         loop {
             let new_max_index = max_index.add(term, Boundary::Cursor, 1);
-            if new_max_index == max_index || grid.index(new_max_index).hyperlink() != link {
+            if new_max_index == max_index || app_hyperlink(grid.index(new_max_index)) != link {
                 break;
             } else {
                 max_index = new_max_index
@@ -0,0 +1,666 @@
+//! Importers for the color schemes of other terminal emulators.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{Context as _, Result, anyhow};
+use gpui::{Hsla, Rgba};
+use serde::Deserialize;
+
+use crate::ThemeColors;
+
+/// A file format of color schemes that can be imported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSchemeFormat {
+    /// An iTerm2 `.itermcolors` property list.
+    ITerm,
+    /// A color scheme object from Windows Terminal's settings.
+    WindowsTerminal,
+    /// A base16 scheme, in either the original or the tinted-theming YAML layout.
+    Base16,
+    /// An Alacritty TOML configuration with a `[colors]` table.
+    Alacritty,
+}
+
+impl ColorSchemeFormat {
+    /// Guesses the format of a color scheme file from its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "itermcolors" => Some(Self::ITerm),
+            "json" => Some(Self::WindowsTerminal),
+            "yaml" | "yml" => Some(Self::Base16),
+            "toml" => Some(Self::Alacritty),
+            _ => None,
+        }
+    }
+}
+
+/// A color scheme imported from another terminal emulator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorScheme {
+    pub name: String,
+    pub colors: ThemeColors,
+}
+
+impl ColorScheme {
+    /// Reads a color scheme file, named after the file unless the scheme names itself.
+    pub fn load(path: &Path) -> Result<Self> {
+        let format = ColorSchemeFormat::from_path(path)
+            .with_context(|| format!("unsupported color scheme format: {}", path.display()))?;
+        let content =
+            fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let default_name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self::parse(format, &default_name, &content)
+            .with_context(|| format!("failed to import {}", path.display()))
+    }
+
+    pub fn parse(format: ColorSchemeFormat, default_name: &str, content: &[u8]) -> Result<Self> {
+        let (name, palette) = match format {
+            ColorSchemeFormat::ITerm => parse_iterm(content)?,
+            ColorSchemeFormat::WindowsTerminal => parse_windows_terminal(content)?,
+            ColorSchemeFormat::Base16 => parse_base16(content)?,
+            ColorSchemeFormat::Alacritty => parse_alacritty(content)?,
+        };
+        Ok(Self {
+            name: name
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| default_name.to_string()),
+            colors: palette.into_theme_colors(),
+        })
+    }
+
+    /// Whether the scheme has a light background, and so belongs in a light theme.
+    pub fn is_light(&self) -> bool {
+        self.colors.terminal_background.l > 0.5
+    }
+}
+
+/// The colors every supported format specifies, in ANSI order.
+struct Palette {
+    foreground: Hsla,
+    background: Hsla,
+    bright_foreground: Option<Hsla>,
+    dim_foreground: Option<Hsla>,
+    normal: [Hsla; 8],
+    bright: [Hsla; 8],
+    dim: Option<[Hsla; 8]>,
+}
+
+impl Palette {
+    fn into_theme_colors(self) -> ThemeColors {
+        let background = self.background;
+        let defaults = if background.l > 0.5 {
+            ThemeColors::light()
+        } else {
+            ThemeColors::dark()
+        };
+        let dim_colors = self.dim.unwrap_or_else(|| {
+            let default_normal = [
+                defaults.terminal_ansi_black,
+                defaults.terminal_ansi_red,
+                defaults.terminal_ansi_green,
+                defaults.terminal_ansi_yellow,
+                defaults.terminal_ansi_blue,
+                defaults.terminal_ansi_magenta,
+                defaults.terminal_ansi_cyan,
+                defaults.terminal_ansi_white,
+            ];
+            let default_dim = [
+                defaults.terminal_ansi_dim_black,
+                defaults.terminal_ansi_dim_red,
+                defaults.terminal_ansi_dim_green,
+                defaults.terminal_ansi_dim_yellow,
+                defaults.terminal_ansi_dim_blue,
+                defaults.terminal_ansi_dim_magenta,
+                defaults.terminal_ansi_dim_cyan,
+                defaults.terminal_ansi_dim_white,
+            ];
+            std::array::from_fn(|ix| dim(self.normal[ix], default_normal[ix], default_dim[ix]))
+        });
+        let [black, red, green, yellow, blue, magenta, cyan, white] = self.normal;
+        let [
+            bright_black,
+            bright_red,
+            bright_green,
+            bright_yellow,
+            bright_blue,
+            bright_magenta,
+            bright_cyan,
+            bright_white,
+        ] = self.bright;
+        let [
+            dim_black,
+            dim_red,
+            dim_green,
+            dim_yellow,
+            dim_blue,
+            dim_magenta,
+            dim_cyan,
+            dim_white,
+        ] = dim_colors;
+
+        ThemeColors {
+            terminal_background: background,
+            terminal_foreground: self.foreground,
+            terminal_bright_foreground: self.bright_foreground.unwrap_or(self.foreground),
+            terminal_dim_foreground: self.dim_foreground.unwrap_or_else(|| {
+                dim(
+                    self.foreground,
+                    defaults.terminal_foreground,
+                    defaults.terminal_dim_foreground,
+                )
+            }),
+            terminal_ansi_background: background,
+            terminal_ansi_black: black,
+            terminal_ansi_bright_black: bright_black,
+            terminal_ansi_dim_black: dim_black,
+            terminal_ansi_red: red,
+            terminal_ansi_bright_red: bright_red,
+            terminal_ansi_dim_red: dim_red,
+            terminal_ansi_green: green,
+            terminal_ansi_bright_green: bright_green,
+            terminal_ansi_dim_green: dim_green,
+            terminal_ansi_yellow: yellow,
+            terminal_ansi_bright_yellow: bright_yellow,
+            terminal_ansi_dim_yellow: dim_yellow,
+            terminal_ansi_blue: blue,
+            terminal_ansi_bright_blue: bright_blue,
+            terminal_ansi_dim_blue: dim_blue,
+            terminal_ansi_magenta: magenta,
+            terminal_ansi_bright_magenta: bright_magenta,
+            terminal_ansi_dim_magenta: dim_magenta,
+            terminal_ansi_cyan: cyan,
+            terminal_ansi_bright_cyan: bright_cyan,
+            terminal_ansi_dim_cyan: dim_cyan,
+            terminal_ansi_white: white,
+            terminal_ansi_bright_white: bright_white,
+            terminal_ansi_dim_white: dim_white,
+        }
+    }
+}
+
+/// Derives the dim variant of `color` the way the default palettes pick theirs, a step or two
+/// down the color's scale. The step is the change in saturation and lightness from
+/// `default_normal` to `default_dim`, the same color's variants in the default palette.
+fn dim(color: Hsla, default_normal: Hsla, default_dim: Hsla) -> Hsla {
+    Hsla {
+        s: (color.s + default_dim.s - default_normal.s).clamp(0., 1.),
+        l: (color.l + default_dim.l - default_normal.l).clamp(0., 1.),
+        ..color
+    }
+}
+
+/// Parses a hex color written as `#rrggbb`, `rrggbb` or `0xrrggbb`.
+fn parse_hex(color: &str) -> Result<Hsla> {
+    let trimmed = color.trim();
+    let hex = trimmed
+        .strip_prefix('#')
+        .or_else(|| trimmed.strip_prefix("0x"))
+        .unwrap_or(trimmed);
+    Rgba::try_from(format!("#{hex}").as_str())
+        .map(Hsla::from)
+        .map_err(|err| anyhow!("invalid color {color:?}: {err}"))
+}
+
+fn parse_hex_array(colors: [&String; 8]) -> Result<[Hsla; 8]> {
+    let mut parsed = [Hsla::default(); 8];
+    for (parsed, color) in parsed.iter_mut().zip(colors) {
+        *parsed = parse_hex(color)?;
+    }
+    Ok(parsed)
+}
+
+#[derive(Deserialize)]
+struct ITermColor {
+    #[serde(rename = "Red Component")]
+    red: f32,
+    #[serde(rename = "Green Component")]
+    green: f32,
+    #[serde(rename = "Blue Component")]
+    blue: f32,
+}
+
+fn parse_iterm(content: &[u8]) -> Result<(Option<String>, Palette)> {
+    let colors: HashMap<String, ITermColor> = plist::from_bytes(content)?;
+    let color = |key: &str| -> Result<Hsla> {
+        let color = colors
+            .get(key)
+            .with_context(|| format!("missing {key:?}"))?;
+        Ok(Rgba {
+            r: color.red,
+            g: color.green,
+            b: color.blue,
+            a: 1.,
+        }
+        .into())
+    };
+    let ansi = |offset: usize| -> Result<[Hsla; 8]> {
+        let mut ansi = [Hsla::default(); 8];
+        for (index, ansi) in ansi.iter_mut().enumerate() {
+            *ansi = color(&format!("Ansi {} Color", index + offset))?;
+        }
+        Ok(ansi)
+    };
+
+    let palette = Palette {
+        foreground: color("Foreground Color")?,
+        background: color("Background Color")?,
+        bright_foreground: None,
+        dim_foreground: None,
+        normal: ansi(0)?,
+        bright: ansi(8)?,
+        dim: None,
+    };
+    Ok((None, palette))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WindowsTerminalScheme {
+    name: Option<String>,
+    foreground: String,
+    background: String,
+    black: String,
+    red: String,
+    green: String,
+    yellow: String,
+    blue: String,
+    purple: String,
+    cyan: String,
+    white: String,
+    bright_black: String,
+    bright_red: String,
+    bright_green: String,
+    bright_yellow: String,
+    bright_blue: String,
+    bright_purple: String,
+    bright_cyan: String,
+    bright_white: String,
+}
+
+fn parse_windows_terminal(content: &[u8]) -> Result<(Option<String>, Palette)> {
+    let scheme: WindowsTerminalScheme = serde_json::from_slice(content)?;
+    let palette = Palette {
+        foreground: parse_hex(&scheme.foreground)?,
+        background: parse_hex(&scheme.background)?,
+        bright_foreground: None,
+        dim_foreground: None,
+        normal: parse_hex_array([
+            &scheme.black,
+            &scheme.red,
+            &scheme.green,
+            &scheme.yellow,
+            &scheme.blue,
+            &scheme.purple,
+            &scheme.cyan,
+            &scheme.white,
+        ])?,
+        bright: parse_hex_array([
+            &scheme.bright_black,
+            &scheme.bright_red,
+            &scheme.bright_green,
+            &scheme.bright_yellow,
+            &scheme.bright_blue,
+            &scheme.bright_purple,
+            &scheme.bright_cyan,
+            &scheme.bright_white,
+        ])?,
+        dim: None,
+    };
+    Ok((scheme.name, palette))
+}
+
+#[derive(Deserialize)]
+struct Base16Scheme {
+    /// The scheme's name in the original layout.
+    scheme: Option<String>,
+    /// The scheme's name in the tinted-theming layout.
+    name: Option<String>,
+    /// The colors in the tinted-theming layout; the original layout has them at the top level.
+    palette: Option<HashMap<String, String>>,
+    #[serde(flatten)]
+    rest: HashMap<String, serde_yaml::Value>,
+}
+
+fn parse_base16(content: &[u8]) -> Result<(Option<String>, Palette)> {
+    let scheme: Base16Scheme = serde_yaml::from_slice(content)?;
+    let base = |index: usize| -> Result<Hsla> {
+        let key = format!("base{index:02X}");
+        let color = match &scheme.palette {
+            Some(palette) => palette.get(&key).map(String::as_str),
+            None => scheme.rest.get(&key).and_then(serde_yaml::Value::as_str),
+        };
+        parse_hex(color.with_context(|| format!("missing {key:?}"))?)
+    };
+
+    // The mapping used by base16-shell and most base16 terminal templates.
+    let palette = Palette {
+        foreground: base(0x05)?,
+        background: base(0x00)?,
+        bright_foreground: None,
+        dim_foreground: None,
+        normal: [
+            base(0x00)?,
+            base(0x08)?,
+            base(0x0B)?,
+            base(0x0A)?,
+            base(0x0D)?,
+            base(0x0E)?,
+            base(0x0C)?,
+            base(0x05)?,
+        ],
+        bright: [
+            base(0x03)?,
+            base(0x08)?,
+            base(0x0B)?,
+            base(0x0A)?,
+            base(0x0D)?,
+            base(0x0E)?,
+            base(0x0C)?,
+            base(0x07)?,
+        ],
+        dim: None,
+    };
+    Ok((scheme.name.or(scheme.scheme), palette))
+}
+
+#[derive(Deserialize)]
+struct AlacrittyConfig {
+    colors: AlacrittyColors,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyColors {
+    primary: AlacrittyPrimaryColors,
+    normal: AlacrittyAnsiColors,
+    bright: AlacrittyAnsiColors,
+    dim: Option<AlacrittyAnsiColors>,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyPrimaryColors {
+    foreground: String,
+    background: String,
+    bright_foreground: Option<String>,
+    dim_foreground: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyAnsiColors {
+    black: String,
+    red: String,
+    green: String,
+    yellow: String,
+    blue: String,
+    magenta: String,
+    cyan: String,
+    white: String,
+}
+
+impl AlacrittyAnsiColors {
+    fn parse(&self) -> Result<[Hsla; 8]> {
+        parse_hex_array([
+            &self.black,
+            &self.red,
+            &self.green,
+            &self.yellow,
+            &self.blue,
+            &self.magenta,
+            &self.cyan,
+            &self.white,
+        ])
+    }
+}
+
+fn parse_alacritty(content: &[u8]) -> Result<(Option<String>, Palette)> {
+    let config: AlacrittyConfig = toml::from_str(std::str::from_utf8(content)?)?;
+    let colors = config.colors;
+    let palette = Palette {
+        foreground: parse_hex(&colors.primary.foreground)?,
+        background: parse_hex(&colors.primary.background)?,
+        bright_foreground: colors
+            .primary
+            .bright_foreground
+            .as_deref()
+            .map(parse_hex)
+            .transpose()?,
+        dim_foreground: colors
+            .primary
+            .dim_foreground
+            .as_deref()
+            .map(parse_hex)
+            .transpose()?,
+        normal: colors.normal.parse()?,
+        bright: colors.bright.parse()?,
+        dim: colors
+            .dim
+            .as_ref()
+            .map(AlacrittyAnsiColors::parse)
+            .transpose()?,
+    };
+    Ok((None, palette))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(color: &str) -> Hsla {
+        parse_hex(color).unwrap()
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        let format = |path: &str| ColorSchemeFormat::from_path(Path::new(path));
+        assert_eq!(
+            format("Dracula.itermcolors"),
+            Some(ColorSchemeFormat::ITerm)
+        );
+        assert_eq!(
+            format("scheme.JSON"),
+            Some(ColorSchemeFormat::WindowsTerminal)
+        );
+        assert_eq!(format("ocean.yml"), Some(ColorSchemeFormat::Base16));
+        assert_eq!(format("alacritty.toml"), Some(ColorSchemeFormat::Alacritty));
+        assert_eq!(format("README.md"), None);
+    }
+
+    #[test]
+    fn test_import_iterm() {
+        let mut entries = String::new();
+        let mut add_color = |key: &str, red: f32| {
+            entries.push_str(&format!(
+                "<key>{key}</key><dict>\
+                 <key>Red Component</key><real>{red}</real>\
+                 <key>Green Component</key><real>0</real>\
+                 <key>Blue Component</key><real>0</real>\
+                 </dict>"
+            ));
+        };
+        for index in 0..16 {
+            add_color(&format!("Ansi {index} Color"), index as f32 / 15.);
+        }
+        add_color("Foreground Color", 1.);
+        add_color("Background Color", 0.);
+        let content = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict>{entries}</dict></plist>"#
+        );
+
+        let scheme =
+            ColorScheme::parse(ColorSchemeFormat::ITerm, "Reds", content.as_bytes()).unwrap();
+        assert_eq!(scheme.name, "Reds");
+        assert_eq!(scheme.colors.terminal_foreground, hex("#ff0000"));
+        assert_eq!(scheme.colors.terminal_ansi_bright_white, hex("#ff0000"));
+        assert!(!scheme.is_light());
+    }
+
+    #[test]
+    fn test_import_windows_terminal() {
+        let content = r##"{
+            "name": "Campbell",
+            "foreground": "#CCCCCC",
+            "background": "#0C0C0C",
+            "cursorColor": "#FFFFFF",
+            "black": "#0C0C0C",
+            "red": "#C50F1F",
+            "green": "#13A10E",
+            "yellow": "#C19C00",
+            "blue": "#0037DA",
+            "purple": "#881798",
+            "cyan": "#3A96DD",
+            "white": "#CCCCCC",
+            "brightBlack": "#767676",
+            "brightRed": "#E74856",
+            "brightGreen": "#16C60C",
+            "brightYellow": "#F9F1A5",
+            "brightBlue": "#3B78FF",
+            "brightPurple": "#B4009E",
+            "brightCyan": "#61D6D6",
+            "brightWhite": "#F2F2F2"
+        }"##;
+
+        let scheme = ColorScheme::parse(
+            ColorSchemeFormat::WindowsTerminal,
+            "file",
+            content.as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(scheme.name, "Campbell");
+        assert_eq!(scheme.colors.terminal_ansi_magenta, hex("#881798"));
+        assert_eq!(scheme.colors.terminal_ansi_bright_magenta, hex("#B4009E"));
+        // Dim colors are derived by stepping down like the default dark palette does.
+        let dim_red = scheme.colors.terminal_ansi_dim_red;
+        assert!(dim_red.l < scheme.colors.terminal_ansi_red.l);
+    }
+
+    #[test]
+    fn test_derived_dims_match_default_palettes() {
+        for defaults in [ThemeColors::light(), ThemeColors::dark()] {
+            let palette = Palette {
+                foreground: defaults.terminal_foreground,
+                background: defaults.terminal_background,
+                bright_foreground: None,
+                dim_foreground: None,
+                normal: [
+                    defaults.terminal_ansi_black,
+                    defaults.terminal_ansi_red,
+                    defaults.terminal_ansi_green,
+                    defaults.terminal_ansi_yellow,
+                    defaults.terminal_ansi_blue,
+                    defaults.terminal_ansi_magenta,
+                    defaults.terminal_ansi_cyan,
+                    defaults.terminal_ansi_white,
+                ],
+                bright: [defaults.terminal_ansi_bright_black; 8],
+                dim: None,
+            };
+            let colors = palette.into_theme_colors();
+
+            for (derived, expected) in [
+                (
+                    colors.terminal_dim_foreground,
+                    defaults.terminal_dim_foreground,
+                ),
+                (colors.terminal_ansi_dim_red, defaults.terminal_ansi_dim_red),
+                (
+                    colors.terminal_ansi_dim_blue,
+                    defaults.terminal_ansi_dim_blue,
+                ),
+                (
+                    colors.terminal_ansi_dim_white,
+                    defaults.terminal_ansi_dim_white,
+                ),
+            ] {
+                assert!((derived.l - expected.l).abs() < 1e-4);
+                assert!((derived.s - expected.s).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_import_base16() {
+        let colors = (0..16)
+            .map(|index| {
+                format!(
+                    "base{index:02X}: \"{:02x}{:02x}{:02x}\"\n",
+                    index * 16,
+                    0,
+                    0
+                )
+            })
+            .collect::<String>();
+        let original = format!("scheme: \"Ocean\"\nauthor: \"Someone\"\n{colors}");
+        let scheme =
+            ColorScheme::parse(ColorSchemeFormat::Base16, "file", original.as_bytes()).unwrap();
+        assert_eq!(scheme.name, "Ocean");
+        assert_eq!(scheme.colors.terminal_background, hex("#000000"));
+        assert_eq!(scheme.colors.terminal_foreground, hex("#500000"));
+        assert_eq!(scheme.colors.terminal_ansi_red, hex("#800000"));
+        assert_eq!(scheme.colors.terminal_ansi_bright_black, hex("#300000"));
+
+        let palette = colors
+            .lines()
+            .map(|line| format!("  {line}\n"))
+            .collect::<String>();
+        let tinted = format!("system: \"base16\"\nname: \"Ocean Tinted\"\npalette:\n{palette}");
+        let scheme =
+            ColorScheme::parse(ColorSchemeFormat::Base16, "file", tinted.as_bytes()).unwrap();
+        assert_eq!(scheme.name, "Ocean Tinted");
+        assert_eq!(scheme.colors.terminal_ansi_blue, hex("#d00000"));
+    }
+
+    #[test]
+    fn test_import_alacritty() {
+        let content = r##"
+[colors.primary]
+background = "#fcfcfc"
+foreground = "0x5c6166"
+
+[colors.normal]
+black = "#000000"
+red = "#ea6c6d"
+green = "#6cbf43"
+yellow = "#eca944"
+blue = "#3199e1"
+magenta = "#9e75c7"
+cyan = "#46ba94"
+white = "#bababa"
+
+[colors.bright]
+black = "#686868"
+red = "#f07171"
+green = "#86b300"
+yellow = "#f2ae49"
+blue = "#399ee6"
+magenta = "#a37acc"
+cyan = "#4cbf99"
+white = "#d1d1d1"
+
+[colors.dim]
+black = "#111111"
+red = "#222222"
+green = "#333333"
+yellow = "#444444"
+blue = "#555555"
+magenta = "#666666"
+cyan = "#777777"
+white = "#888888"
+"##;
+
+        let scheme = ColorScheme::parse(
+            ColorSchemeFormat::Alacritty,
+            "ayu_light",
+            content.as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(scheme.name, "ayu_light");
+        assert!(scheme.is_light());
+        assert_eq!(scheme.colors.terminal_foreground, hex("#5c6166"));
+        assert_eq!(scheme.colors.terminal_ansi_dim_red, hex("#222222"));
+        assert_eq!(scheme.colors.terminal_bright_foreground, hex("#5c6166"));
+    }
+}
@@ -1,4 +1,5 @@
 use gpui::{App, Global, Hsla, Rgba, SharedString};
+use settings::ThemeColorsContent;
 
 pub trait ActiveColors {
     fn terminal_colors(&self) -> &ThemeColors;
@@ -360,6 +361,44 @@ pub struct ThemeColors {
 }
 
 impl Global for ThemeColors {}
+
+/// Invokes `$callback` with the name of every color, which [`ThemeColorsContent`] shares.
+macro_rules! with_color_fields {
+    ($callback:ident) => {
+        $callback!(
+            terminal_background,
+            terminal_foreground,
+            terminal_bright_foreground,
+            terminal_dim_foreground,
+            terminal_ansi_background,
+            terminal_ansi_black,
+            terminal_ansi_bright_black,
+            terminal_ansi_dim_black,
+            terminal_ansi_red,
+            terminal_ansi_bright_red,
+            terminal_ansi_dim_red,
+            terminal_ansi_green,
+            terminal_ansi_bright_green,
+            terminal_ansi_dim_green,
+            terminal_ansi_yellow,
+            terminal_ansi_bright_yellow,
+            terminal_ansi_dim_yellow,
+            terminal_ansi_blue,
+            terminal_ansi_bright_blue,
+            terminal_ansi_dim_blue,
+            terminal_ansi_magenta,
+            terminal_ansi_bright_magenta,
+            terminal_ansi_dim_magenta,
+            terminal_ansi_cyan,
+            terminal_ansi_bright_cyan,
+            terminal_ansi_dim_cyan,
+            terminal_ansi_white,
+            terminal_ansi_bright_white,
+            terminal_ansi_dim_white
+        )
+    };
+}
+
 /// The default colors for the theme.
 ///
 /// Themes that do not specify all colors are refined off of these defaults.
@@ -439,6 +478,55 @@ impl ThemeColors {
             terminal_ansi_dim_white: neutral().dark().step_10(),
         }
     }
+
+    /// Overrides the colors specified by a theme's `terminal.*` keys, keeping the rest.
+    pub fn refine(&mut self, content: &ThemeColorsContent) {
+        macro_rules! refine {
+            ($($field:ident),*) => {
+                $(
+                    if let Some(color) = content.$field.as_deref().and_then(parse_color) {
+                        self.$field = color;
+                    }
+                )*
+            };
+        }
+        with_color_fields!(refine);
+    }
+
+    /// Returns the colors as the `terminal.*` keys of a theme.
+    pub fn to_content(&self) -> ThemeColorsContent {
+        let mut content = ThemeColorsContent::default();
+        macro_rules! to_content {
+            ($($field:ident),*) => {
+                $(content.$field = Some(to_hex(self.$field));)*
+            };
+        }
+        with_color_fields!(to_content);
+        content
+    }
+}
+
+fn parse_color(color: &str) -> Option<Hsla> {
+    Rgba::try_from(color)
+        .map(Hsla::from)
+        .inspect_err(|err| log::warn!("invalid terminal color {color:?}: {err}"))
+        .ok()
+}
+
+fn to_hex(color: Hsla) -> String {
+    let color = Rgba::from(color);
+    let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+    let (r, g, b, a) = (
+        channel(color.r),
+        channel(color.g),
+        channel(color.b),
+        channel(color.a),
+    );
+    if a == u8::MAX {
+        format!("#{r:02x}{g:02x}{b:02x}")
+    } else {
+        format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+    }
 }
 
 type StaticColorScale = [&'static str; 12];
@@ -2623,3 +2711,42 @@ pub(crate) fn white() -> ColorScaleSet {
     .try_into()
     .unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refine_overrides_only_specified_colors() {
+        let content = ThemeColorsContent {
+            terminal_ansi_red: Some("#f7768e".into()),
+            terminal_background: Some("not a color".into()),
+            ..Default::default()
+        };
+        let mut colors = ThemeColors::dark();
+        colors.refine(&content);
+
+        let expected = ThemeColors {
+            terminal_ansi_red: Rgba::try_from("#f7768e").unwrap().into(),
+            ..ThemeColors::dark()
+        };
+        assert_eq!(colors, expected);
+    }
+
+    #[test]
+    fn test_to_content_round_trips() {
+        let content = ThemeColorsContent {
+            terminal_ansi_red: Some("#f7768e".into()),
+            ..Default::default()
+        };
+        let mut colors = ThemeColors::light();
+        colors.refine(&content);
+
+        let content = colors.to_content();
+        assert_eq!(content.terminal_ansi_red.as_deref(), Some("#f7768e"));
+
+        let mut round_tripped = ThemeColors::dark();
+        round_tripped.refine(&content);
+        assert_eq!(round_tripped.to_content(), content);
+    }
+}
@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 
 /// The mappings defined in this file where created from reading the alacritty source
-use gpui::Keystroke;
+use gpui::{Keystroke, Modifiers};
 
 use crate::Modes;
 
@@ -44,11 +44,38 @@ impl TerminalModifiers {
     }
 }
 
+/// The kinds of key events the kitty keyboard protocol tells apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum KeyEventType {
+    Press,
+    Repeat,
+    Release,
+}
+
+impl KeyEventType {
+    fn kitty_code(self) -> u8 {
+        match self {
+            KeyEventType::Press => 1,
+            KeyEventType::Repeat => 2,
+            KeyEventType::Release => 3,
+        }
+    }
+}
+
 pub(crate) fn to_esc_str(
     keystroke: &Keystroke,
     mode: Modes,
     option_as_meta: bool,
 ) -> Option<Cow<'static, str>> {
+    if let Some(esc_str) = to_kitty_esc_str(keystroke, mode, KeyEventType::Press, option_as_meta) {
+        return Some(Cow::Owned(esc_str));
+    }
+    if mode.contains(Modes::MODIFY_OTHER_KEYS)
+        && let Some(esc_str) = modify_other_keys_esc_str(keystroke, option_as_meta)
+    {
+        return Some(Cow::Owned(esc_str));
+    }
+
     let modifiers = TerminalModifiers::new(keystroke);
 
     // Manual Bindings including modifiers
@@ -253,10 +280,270 @@ fn modifier_code(keystroke: &Keystroke) -> u32 {
     modifier_code + 1
 }
 
+/// Encodes a modified key as `CSI 27 ; modifier code ; character ~`, like xterm with
+/// modifyOtherKeys set to 2. Keys with only Shift held still send their text.
+/// from: https://invisible-island.net/xterm/modified-keys.html
+fn modify_other_keys_esc_str(keystroke: &Keystroke, option_as_meta: bool) -> Option<String> {
+    let modifiers = &keystroke.modifiers;
+    if modifiers.platform || modifiers.function || !(modifiers.control || modifiers.alt) {
+        return None;
+    }
+    // Without Option as Meta, Option composes the text on macOS rather than acting as Alt.
+    if cfg!(target_os = "macos") && !option_as_meta && modifiers.alt {
+        return None;
+    }
+
+    let character = match keystroke.key.as_str() {
+        "tab" => '\t',
+        "enter" => '\r',
+        "escape" => '\x1b',
+        "space" => ' ',
+        "backspace" => '\x7f',
+        key => {
+            let key = single_char(key).filter(|key| !key.is_control())?;
+            if modifiers.shift {
+                keystroke
+                    .key_char
+                    .as_deref()
+                    .and_then(single_char)
+                    .unwrap_or_else(|| key.to_uppercase().next().unwrap_or(key))
+            } else {
+                key
+            }
+        }
+    };
+    Some(format!(
+        "\x1b[27;{};{}~",
+        modifier_code(keystroke),
+        u32::from(character)
+    ))
+}
+
+const KITTY_SHIFT: u32 = 1;
+const KITTY_ALT: u32 = 1 << 1;
+const KITTY_CTRL: u32 = 1 << 2;
+const KITTY_SUPER: u32 = 1 << 3;
+
+/// Encodes a key event with the kitty keyboard protocol, when the enhancements `mode` enables
+/// apply to it. Returns `None` for keys still sent as legacy input or text.
+/// from: https://sw.kovidgoyal.net/kitty/keyboard-protocol/
+pub(crate) fn to_kitty_esc_str(
+    keystroke: &Keystroke,
+    mode: Modes,
+    event_type: KeyEventType,
+    option_as_meta: bool,
+) -> Option<String> {
+    if !mode.intersects(
+        Modes::DISAMBIGUATE_ESC_CODES | Modes::REPORT_EVENT_TYPES | Modes::REPORT_ALL_KEYS_AS_ESC,
+    ) || event_type == KeyEventType::Release && !mode.contains(Modes::REPORT_EVENT_TYPES)
+    {
+        return None;
+    }
+
+    let key = keystroke.key.as_str();
+    let text = keystroke
+        .key_char
+        .as_deref()
+        .filter(|text| !text.is_empty() && !text.chars().any(char::is_control));
+    let mut modifiers = kitty_modifiers(&keystroke.modifiers);
+    // Without Option as Meta, Option composes the text on macOS rather than acting as Alt.
+    if cfg!(target_os = "macos") && !option_as_meta && text.is_some() {
+        modifiers &= !KITTY_ALT;
+    }
+
+    let event_type = (mode.contains(Modes::REPORT_EVENT_TYPES)
+        && event_type != KeyEventType::Press)
+        .then_some(event_type);
+    let report_all_keys = mode.contains(Modes::REPORT_ALL_KEYS_AS_ESC);
+    let is_legacy_text_key = matches!(key, "enter" | "tab" | "backspace");
+    let encode = match event_type {
+        // Keep these usable by shells left behind by applications that did not restore the mode.
+        Some(KeyEventType::Release) => report_all_keys || !is_legacy_text_key,
+        _ => {
+            report_all_keys
+                || mode.contains(Modes::DISAMBIGUATE_ESC_CODES)
+                    && (key == "escape"
+                        || modifiers != 0 && (modifiers != KITTY_SHIFT || is_legacy_text_key))
+                // Unmodified presses of keys without text keep their legacy encoding, which
+                // follows the application cursor mode.
+                || text.is_none()
+                    && (modifiers != 0 || event_type.is_some())
+                    && functional_key(key, "").is_some()
+        }
+    };
+    if !encode {
+        return None;
+    }
+
+    let text = text.filter(|_| {
+        mode.contains(Modes::REPORT_ASSOCIATED_TEXT) && event_type != Some(KeyEventType::Release)
+    });
+    let one = if modifiers == 0 && event_type.is_none() && text.is_none() {
+        ""
+    } else {
+        "1"
+    };
+    let (code, terminator) = functional_key(key, one)
+        .or_else(|| control_key(key).map(|code| (code.to_string(), 'u')))
+        .or_else(|| textual_key(keystroke, mode).map(|code| (code, 'u')))
+        .or_else(|| (report_all_keys && text.is_some()).then(|| ("0".to_string(), 'u')))?;
+
+    Some(kitty_sequence(
+        &code, terminator, modifiers, event_type, text,
+    ))
+}
+
+/// Encodes the modifier keys pressed and released between `previous` and `modifiers`, which
+/// the kitty keyboard protocol only reports with all keys reported as escape codes.
+pub(crate) fn to_kitty_modifier_esc_strs(
+    previous: &Modifiers,
+    modifiers: &Modifiers,
+    mode: Modes,
+) -> Vec<String> {
+    if !mode.contains(Modes::REPORT_ALL_KEYS_AS_ESC) {
+        return Vec::new();
+    }
+
+    let report_event_types = mode.contains(Modes::REPORT_EVENT_TYPES);
+    [
+        (previous.shift, modifiers.shift, 57441),
+        (previous.control, modifiers.control, 57442),
+        (previous.alt, modifiers.alt, 57443),
+        (previous.platform, modifiers.platform, 57444),
+    ]
+    .into_iter()
+    .filter(|&(was_pressed, pressed, _)| was_pressed != pressed && (pressed || report_event_types))
+    .map(|(_, pressed, code)| {
+        let event_type = (!pressed).then_some(KeyEventType::Release);
+        kitty_sequence(
+            &code.to_string(),
+            'u',
+            kitty_modifiers(modifiers),
+            event_type,
+            None,
+        )
+    })
+    .collect()
+}
+
+/// Formats `CSI code ; modifiers : event-type ; text terminator`, leaving out trailing defaults.
+fn kitty_sequence(
+    code: &str,
+    terminator: char,
+    modifiers: u32,
+    event_type: Option<KeyEventType>,
+    text: Option<&str>,
+) -> String {
+    let mut esc_str = format!("\x1b[{code}");
+    if modifiers != 0 || event_type.is_some() || text.is_some() {
+        esc_str.push_str(&format!(";{}", modifiers + 1));
+    }
+    if let Some(event_type) = event_type {
+        esc_str.push_str(&format!(":{}", event_type.kitty_code()));
+    }
+    if let Some(text) = text {
+        let codepoints = text
+            .chars()
+            .map(|c| u32::from(c).to_string())
+            .collect::<Vec<_>>();
+        esc_str.push_str(&format!(";{}", codepoints.join(":")));
+    }
+    esc_str.push(terminator);
+    esc_str
+}
+
+fn kitty_modifiers(modifiers: &Modifiers) -> u32 {
+    let mut kitty_modifiers = 0;
+    if modifiers.shift {
+        kitty_modifiers |= KITTY_SHIFT;
+    }
+    if modifiers.alt {
+        kitty_modifiers |= KITTY_ALT;
+    }
+    if modifiers.control {
+        kitty_modifiers |= KITTY_CTRL;
+    }
+    if modifiers.platform {
+        kitty_modifiers |= KITTY_SUPER;
+    }
+    kitty_modifiers
+}
+
+/// The code and terminator of keys without text, `one` being the code of the keys whose legacy
+/// encoding has none, written only when followed by parameters.
+fn functional_key(key: &str, one: &str) -> Option<(String, char)> {
+    let (code, terminator) = match key {
+        "pageup" => ("5", '~'),
+        "pagedown" => ("6", '~'),
+        "insert" => ("2", '~'),
+        "delete" => ("3", '~'),
+        "home" => (one, 'H'),
+        "end" => (one, 'F'),
+        "up" => (one, 'A'),
+        "down" => (one, 'B'),
+        "right" => (one, 'C'),
+        "left" => (one, 'D'),
+        "f1" => (one, 'P'),
+        "f2" => (one, 'Q'),
+        // The legacy `CSI R` of F3 is ambiguous with cursor position reports.
+        "f3" => ("13", '~'),
+        "f4" => (one, 'S'),
+        "f5" => ("15", '~'),
+        "f6" => ("17", '~'),
+        "f7" => ("18", '~'),
+        "f8" => ("19", '~'),
+        "f9" => ("20", '~'),
+        "f10" => ("21", '~'),
+        "f11" => ("23", '~'),
+        "f12" => ("24", '~'),
+        _ => {
+            // F13 to F35 are in the private use area.
+            let number = key.strip_prefix('f')?.parse::<u32>().ok()?;
+            return (13..=35)
+                .contains(&number)
+                .then(|| ((57376 + number - 13).to_string(), 'u'));
+        }
+    };
+    Some((code.to_string(), terminator))
+}
+
+fn control_key(key: &str) -> Option<u32> {
+    match key {
+        "tab" => Some(9),
+        "enter" => Some(13),
+        "escape" => Some(27),
+        "space" => Some(32),
+        "backspace" => Some(127),
+        _ => None,
+    }
+}
+
+/// The code of a key with text, the unshifted character, followed by the shifted one when
+/// alternate keys are reported.
+fn textual_key(keystroke: &Keystroke, mode: Modes) -> Option<String> {
+    let key = single_char(&keystroke.key)?;
+    let code = u32::from(key.to_lowercase().next().unwrap_or(key));
+    let shifted = keystroke
+        .key_char
+        .as_deref()
+        .and_then(single_char)
+        .unwrap_or_else(|| key.to_uppercase().next().unwrap_or(key));
+    let shifted = u32::from(shifted);
+    if mode.contains(Modes::REPORT_ALTERNATE_KEYS) && keystroke.modifiers.shift && shifted != code {
+        Some(format!("{code}:{shifted}"))
+    } else {
+        Some(code.to_string())
+    }
+}
+
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    let c = chars.next()?;
+    chars.next().is_none().then_some(c)
+}
+
 #[cfg(test)]
 mod test {
-    use gpui::Modifiers;
-
     use super::*;
 
     #[test]
@@ -393,6 +680,64 @@ mod test {
         assert_eq!(to_esc_str(&regular_enter, mode, false), Some("\x0d".into()));
     }
 
+    #[test]
+    fn test_modify_other_keys() {
+        let mode = Modes::MODIFY_OTHER_KEYS;
+        assert_eq!(
+            to_esc_str(&Keystroke::parse("ctrl-a").unwrap(), mode, false),
+            Some("\x1b[27;5;97~".into())
+        );
+        assert_eq!(
+            to_esc_str(&Keystroke::parse("ctrl-shift-a").unwrap(), mode, false),
+            Some("\x1b[27;6;65~".into())
+        );
+        assert_eq!(
+            to_esc_str(&Keystroke::parse("alt-a").unwrap(), mode, true),
+            Some("\x1b[27;3;97~".into())
+        );
+        assert_eq!(
+            to_esc_str(&Keystroke::parse("ctrl-enter").unwrap(), mode, false),
+            Some("\x1b[27;5;13~".into())
+        );
+        assert_eq!(
+            to_esc_str(&Keystroke::parse("ctrl-space").unwrap(), mode, false),
+            Some("\x1b[27;5;32~".into())
+        );
+
+        // Unmodified and shifted keys send their text, other keys keep their encoding.
+        assert_eq!(
+            to_esc_str(&Keystroke::parse("a").unwrap(), mode, false),
+            None
+        );
+        assert_eq!(
+            to_esc_str(&Keystroke::parse("shift-a").unwrap(), mode, false),
+            None
+        );
+        assert_eq!(
+            to_esc_str(&Keystroke::parse("shift-enter").unwrap(), mode, false),
+            Some("\x0a".into())
+        );
+        assert_eq!(
+            to_esc_str(&Keystroke::parse("ctrl-up").unwrap(), mode, false),
+            Some("\x1b[1;5A".into())
+        );
+
+        // Without the mode, control characters are sent as before, and the kitty keyboard
+        // protocol takes precedence over it.
+        assert_eq!(
+            to_esc_str(&Keystroke::parse("ctrl-a").unwrap(), Modes::NONE, false),
+            Some("\x01".into())
+        );
+        assert_eq!(
+            to_esc_str(
+                &Keystroke::parse("ctrl-a").unwrap(),
+                mode | Modes::DISAMBIGUATE_ESC_CODES,
+                false
+            ),
+            Some("\x1b[97;5u".into())
+        );
+    }
+
     #[test]
     fn test_modifier_code_calc() {
         //   Code     Modifiers
@@ -417,4 +762,153 @@ mod test {
             modifier_code(&Keystroke::parse("shift-ctrl-alt-a").unwrap())
         );
     }
+
+    fn keystroke(source: &str, key_char: Option<&str>) -> Keystroke {
+        Keystroke {
+            key_char: key_char.map(str::to_string),
+            ..Keystroke::parse(source).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_kitty_disambiguate_esc_codes() {
+        let mode = Modes::DISAMBIGUATE_ESC_CODES;
+
+        // Ctrl-I and Tab are told apart, while plain Tab and text are still sent as before.
+        assert_eq!(
+            to_esc_str(&keystroke("ctrl-i", None), mode, false),
+            Some("\x1b[105;5u".into())
+        );
+        assert_eq!(
+            to_esc_str(&keystroke("tab", None), mode, false),
+            Some("\x09".into())
+        );
+        assert_eq!(
+            to_esc_str(&keystroke("shift-tab", None), mode, false),
+            Some("\x1b[9;2u".into())
+        );
+        assert_eq!(
+            to_esc_str(&keystroke("escape", None), mode, false),
+            Some("\x1b[27u".into())
+        );
+        assert_eq!(
+            to_esc_str(&keystroke("ctrl-shift-a", None), mode, false),
+            Some("\x1b[97;6u".into())
+        );
+        assert_eq!(to_esc_str(&keystroke("a", Some("a")), mode, false), None);
+        assert_eq!(
+            to_esc_str(&keystroke("shift-a", Some("A")), mode, false),
+            None
+        );
+
+        assert_eq!(
+            to_esc_str(&keystroke("up", None), mode | Modes::APP_CURSOR, false),
+            Some("\x1bOA".into())
+        );
+        assert_eq!(
+            to_esc_str(&keystroke("ctrl-up", None), mode, false),
+            Some("\x1b[1;5A".into())
+        );
+        assert_eq!(
+            to_esc_str(&keystroke("ctrl-f3", None), mode, false),
+            Some("\x1b[13;5~".into())
+        );
+    }
+
+    #[test]
+    fn test_kitty_report_event_types() {
+        let mode = Modes::DISAMBIGUATE_ESC_CODES | Modes::REPORT_EVENT_TYPES;
+        let a = keystroke("a", Some("a"));
+        assert_eq!(to_kitty_esc_str(&a, mode, KeyEventType::Press, false), None);
+        assert_eq!(
+            to_kitty_esc_str(&a, mode, KeyEventType::Release, false),
+            Some("\x1b[97;1:3u".into())
+        );
+        assert_eq!(
+            to_kitty_esc_str(
+                &keystroke("ctrl-a", None),
+                mode,
+                KeyEventType::Repeat,
+                false
+            ),
+            Some("\x1b[97;5:2u".into())
+        );
+        assert_eq!(
+            to_kitty_esc_str(&keystroke("up", None), mode, KeyEventType::Release, false),
+            Some("\x1b[1;1:3A".into())
+        );
+
+        // Enter, Tab and Backspace are only released when all keys are reported.
+        let enter = keystroke("enter", None);
+        assert_eq!(
+            to_kitty_esc_str(&enter, mode, KeyEventType::Release, false),
+            None
+        );
+        assert_eq!(
+            to_kitty_esc_str(
+                &enter,
+                mode | Modes::REPORT_ALL_KEYS_AS_ESC,
+                KeyEventType::Release,
+                false
+            ),
+            Some("\x1b[13;1:3u".into())
+        );
+
+        // Without event types, releases are not reported at all.
+        assert_eq!(
+            to_kitty_esc_str(
+                &a,
+                Modes::DISAMBIGUATE_ESC_CODES,
+                KeyEventType::Release,
+                false
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_kitty_report_all_keys_as_esc() {
+        let mode = Modes::REPORT_ALL_KEYS_AS_ESC;
+        assert_eq!(
+            to_esc_str(&keystroke("a", Some("a")), mode, false),
+            Some("\x1b[97u".into())
+        );
+        assert_eq!(
+            to_esc_str(&keystroke("enter", None), mode, false),
+            Some("\x1b[13u".into())
+        );
+        assert_eq!(
+            to_esc_str(&keystroke("f13", None), mode, false),
+            Some("\x1b[57376u".into())
+        );
+
+        let shift_a = keystroke("shift-a", Some("A"));
+        assert_eq!(to_esc_str(&shift_a, mode, false), Some("\x1b[97;2u".into()));
+        assert_eq!(
+            to_esc_str(&shift_a, mode | Modes::REPORT_ALTERNATE_KEYS, false),
+            Some("\x1b[97:65;2u".into())
+        );
+        assert_eq!(
+            to_esc_str(&shift_a, mode | Modes::REPORT_ASSOCIATED_TEXT, false),
+            Some("\x1b[97;2;65u".into())
+        );
+    }
+
+    #[test]
+    fn test_kitty_modifier_keys() {
+        let none = Modifiers::default();
+        let ctrl = Modifiers::control();
+        let mode = Modes::REPORT_ALL_KEYS_AS_ESC;
+
+        assert!(to_kitty_modifier_esc_strs(&none, &ctrl, Modes::DISAMBIGUATE_ESC_CODES).is_empty());
+        assert_eq!(
+            to_kitty_modifier_esc_strs(&none, &ctrl, mode),
+            vec!["\x1b[57442;5u"]
+        );
+        assert!(to_kitty_modifier_esc_strs(&ctrl, &none, mode).is_empty());
+        assert_eq!(
+            to_kitty_modifier_esc_strs(&ctrl, &none, mode | Modes::REPORT_EVENT_TYPES),
+            vec!["\x1b[57442;1:3u"]
+        );
+    }
 }
@@ -0,0 +1,96 @@
+//! xterm's modifyOtherKeys setting, which alacritty parses but does not keep.
+//!
+//! The PTY output goes through [`ModifyOtherKeysTracker`] on its way to alacritty, which follows
+//! the setting so the keys typed into the terminal can be encoded the way the application asked.
+
+use vte::{Params, Parser, Perform};
+
+/// Follows the modifyOtherKeys setting of the application in PTY output, see the
+/// [module docs](self).
+pub(crate) struct ModifyOtherKeysTracker {
+    parser: Parser,
+    performer: ModifyOtherKeysPerformer,
+}
+
+impl ModifyOtherKeysTracker {
+    pub(crate) fn new() -> Self {
+        Self {
+            parser: Parser::new(),
+            performer: ModifyOtherKeysPerformer { enabled: None },
+        }
+    }
+
+    /// Parses `output`, returning whether modifyOtherKeys is at level 2 if `output` set it.
+    pub(crate) fn advance(&mut self, output: &[u8]) -> Option<bool> {
+        self.parser.advance(&mut self.performer, output);
+        self.performer.enabled.take()
+    }
+}
+
+struct ModifyOtherKeysPerformer {
+    /// The last setting in the output parsed since it was taken.
+    enabled: Option<bool>,
+}
+
+impl Perform for ModifyOtherKeysPerformer {
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], _: bool, action: char) {
+        // `CSI > 4 ; Pv m` sets modifyOtherKeys, resetting it without a value, as `CSI > 4 n` does.
+        // Only level 2, which has all modified keys sent as `CSI 27 ; mod ; code ~`, is kept.
+        let mut params = params.iter().map(|param| param[0]);
+        if intermediates != b">" || params.next() != Some(4) {
+            return;
+        }
+        self.enabled = match action {
+            'm' => Some(params.next() == Some(2)),
+            'n' => Some(false),
+            _ => return,
+        };
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], _: bool, byte: u8) {
+        // A full reset (RIS) resets modifyOtherKeys too.
+        if intermediates.is_empty() && byte == b'c' {
+            self.enabled = Some(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracks_modify_other_keys() {
+        let mut tracker = ModifyOtherKeysTracker::new();
+        let settings = [
+            &b"\x1b[>4;2m\x1b[1m"[..],
+            b"\x1b[>4;1m",
+            b"\x1b[>4m",
+            b"\x1b[>4;2m",
+            b"\x1b[>4n",
+            b"\x1b[>4;2m",
+            b"\x1bc",
+            b"\x1b[1m",
+            b"\x1b[>4",
+            b";2m",
+        ]
+        .map(|output| tracker.advance(output));
+        assert_eq!(
+            settings,
+            [
+                Some(true),
+                Some(false),
+                Some(false),
+                Some(true),
+                Some(false),
+                Some(true),
+                Some(false),
+                None,
+                None,
+                Some(true),
+            ]
+        );
+        // Only the last setting in the output counts.
+        assert_eq!(tracker.advance(b"\x1b[>4;2m\x1b[>4n"), Some(false));
+    }
+}
@@ -0,0 +1,467 @@
+//! Shell integration through the FinalTerm (OSC 133) sequences shells emit around prompts, and
+//! the working directory they report with OSC 7.
+//!
+//! Alacritty ignores these sequences, so the PTY output goes through [`ShellIntegrationFilter`]
+//! before alacritty parses it. The filter tags the first cell printed in each region of a command
+//! block with an OSC 8 hyperlink naming the region and the block. The tags live in the grid, so
+//! the anchors move along with scrollback rotation and reflow, and go away with the text.
+
+use std::{
+    path::PathBuf,
+    str,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use vte::{Parser, Perform};
+
+/// The scheme of the hyperlinks tagging [`ShellMark`]s, which are not shown as links.
+const MARK_URI_PREFIX: &str = "alacrterm-mark:";
+/// Ends the hyperlink of the cells printed next.
+const CLOSE_HYPERLINK: &[u8] = b"\x1b]8;;\x07";
+/// The longest application hyperlink restored after a tag inside it. Longer ones end at the tag,
+/// which bounds how much the filter adds to its input.
+const MAX_APP_HYPERLINK_LEN: usize = 1024;
+
+/// A region of a command block, from the prompt start (`OSC 133 ; A`), the command start
+/// (`OSC 133 ; B`) or the output start (`OSC 133 ; C`) to the start of the next region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShellRegion {
+    Prompt,
+    Command,
+    Output,
+}
+
+/// An anchor in the grid: the first cell of a region of the command block `block`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShellMark {
+    pub region: ShellRegion,
+    pub block: u64,
+}
+
+impl ShellMark {
+    pub(crate) fn from_uri(uri: &str) -> Option<Self> {
+        let (region, block) = uri.strip_prefix(MARK_URI_PREFIX)?.split_once(':')?;
+        let region = match region {
+            "prompt" => ShellRegion::Prompt,
+            "command" => ShellRegion::Command,
+            "output" => ShellRegion::Output,
+            _ => return None,
+        };
+        Some(Self {
+            region,
+            block: block.parse().ok()?,
+        })
+    }
+
+    fn uri(self) -> String {
+        let region = match self.region {
+            ShellRegion::Prompt => "prompt",
+            ShellRegion::Command => "command",
+            ShellRegion::Output => "output",
+        };
+        format!("{MARK_URI_PREFIX}{region}:{}", self.block)
+    }
+}
+
+/// What the terminal knows about the command entered at a prompt.
+#[derive(Clone, Debug)]
+pub struct CommandBlock {
+    /// Numbers the prompts of the terminal, starting from 1.
+    pub id: u64,
+    pub executed_at: Option<Instant>,
+    pub finished_at: Option<Instant>,
+    pub exit_code: Option<i32>,
+}
+
+impl CommandBlock {
+    pub(crate) fn new(id: u64) -> Self {
+        Self {
+            id,
+            executed_at: None,
+            finished_at: None,
+            exit_code: None,
+        }
+    }
+
+    /// How long the command ran, or has been running. `None` if no command was run.
+    pub fn duration(&self) -> Option<Duration> {
+        let executed_at = self.executed_at?;
+        let end = self.finished_at.unwrap_or_else(Instant::now);
+        Some(end.saturating_duration_since(executed_at))
+    }
+}
+
+/// A working directory the shell reported with `OSC 7 ; file://host/path`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReportedWorkingDirectory {
+    /// The host the directory is on, `None` if it is this machine. Set when the shell runs inside
+    /// `ssh`, for example.
+    pub host: Option<String>,
+    pub path: PathBuf,
+}
+
+impl ReportedWorkingDirectory {
+    pub(crate) fn from_uri(uri: &str) -> Option<Self> {
+        let rest = uri.strip_prefix("file://")?;
+        let (host, path) = rest.split_at(rest.find('/')?);
+        let path = urlencoding::decode(path).ok()?;
+        // `file:///C:/Users` names `C:/Users` on Windows.
+        #[cfg(windows)]
+        let path = match path.strip_prefix('/') {
+            Some(drive_path) if drive_path.get(1..2) == Some(":") => drive_path.to_string(),
+            _ => path.into_owned(),
+        };
+        Some(Self {
+            host: (!is_local_host(host)).then(|| host.to_string()),
+            path: PathBuf::from(&*path),
+        })
+    }
+}
+
+fn is_local_host(host: &str) -> bool {
+    static LOCAL_HOST_NAME: LazyLock<Option<String>> = LazyLock::new(sysinfo::System::host_name);
+    host.is_empty()
+        || host.eq_ignore_ascii_case("localhost")
+        || LOCAL_HOST_NAME
+            .as_deref()
+            .is_some_and(|name| host.eq_ignore_ascii_case(name))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ShellIntegrationEvent {
+    PromptStarted(u64),
+    CommandExecuted(u64),
+    CommandFinished { block: u64, exit_code: Option<i32> },
+    WorkingDirectoryChanged(ReportedWorkingDirectory),
+}
+
+/// Tags the regions of command blocks in PTY output, see the [module docs](self).
+pub(crate) struct ShellIntegrationFilter {
+    parser: Parser,
+    performer: MarkPerformer,
+}
+
+#[derive(Default)]
+struct MarkPerformer {
+    /// The block of the last prompt.
+    block: u64,
+    /// The region whose first cell is still to be printed, with its tag open.
+    pending: Option<ShellRegion>,
+    /// The sequence reopening the hyperlink of the application, if it has one open.
+    app_hyperlink: Option<Vec<u8>>,
+    /// The bytes to insert after the sequence that was just parsed.
+    insertion: Vec<u8>,
+    /// Whether that sequence ended with an ESC, which the `\` of its string terminator follows.
+    awaiting_string_terminator: bool,
+    events: Vec<ShellIntegrationEvent>,
+}
+
+impl ShellIntegrationFilter {
+    pub(crate) fn new() -> Self {
+        Self {
+            parser: Parser::new(),
+            performer: MarkPerformer::default(),
+        }
+    }
+
+    /// The most input whose filtered output is sure to fit in `output_len` bytes.
+    pub(crate) fn max_input_len(output_len: usize) -> usize {
+        static MAX_INSERTION_LEN: LazyLock<usize> = LazyLock::new(|| {
+            let longest_mark = ShellMark {
+                region: ShellRegion::Command,
+                block: u64::MAX,
+            };
+            let max_open_len = longest_mark.uri().len() + CLOSE_HYPERLINK.len();
+            // A tag, the tag closing its region and the end of a string terminator.
+            max_open_len + MAX_APP_HYPERLINK_LEN.max(CLOSE_HYPERLINK.len()) + 2
+        });
+        // Every sequence the filter inserts after is at least as long as `OSC 8 ; ; BEL`. Input
+        // filtered before can still close a tag, and insert after a split string terminator.
+        let growth_per_byte = 1 + MAX_INSERTION_LEN.div_ceil(CLOSE_HYPERLINK.len());
+        output_len.saturating_sub(2 * *MAX_INSERTION_LEN) / growth_per_byte
+    }
+
+    /// Appends `input` to `output`, tagging the command block regions it starts.
+    pub(crate) fn filter(&mut self, mut input: &[u8], output: &mut Vec<u8>) {
+        let performer = &mut self.performer;
+        while !input.is_empty() {
+            let parsed = if performer.awaiting_string_terminator {
+                performer.awaiting_string_terminator = false;
+                if input[0] != b'\\' {
+                    // The ESC starts another sequence. Complete a string terminator so the
+                    // insertion does not interrupt it, and start it again after the insertion.
+                    output.push(b'\\');
+                    output.append(&mut performer.insertion);
+                    output.push(0x1b);
+                    continue;
+                }
+                self.parser.advance(performer, &input[..1]);
+                1
+            } else if performer.pending.is_some() {
+                // Parse byte by byte to insert the end of the tag right after the first cell.
+                self.parser.advance(performer, &input[..1]);
+                1
+            } else {
+                self.parser.advance_until_terminated(performer, input)
+            };
+
+            output.extend_from_slice(&input[..parsed]);
+            performer.awaiting_string_terminator =
+                !performer.insertion.is_empty() && input[..parsed].last() == Some(&0x1b);
+            input = &input[parsed..];
+            if !performer.awaiting_string_terminator {
+                output.append(&mut performer.insertion);
+            }
+        }
+    }
+
+    pub(crate) fn take_events(&mut self) -> Vec<ShellIntegrationEvent> {
+        std::mem::take(&mut self.performer.events)
+    }
+}
+
+impl MarkPerformer {
+    fn start_region(&mut self, region: ShellRegion) {
+        let mark = ShellMark {
+            region,
+            block: self.block,
+        };
+        self.pending = Some(region);
+        self.open_tag(mark);
+    }
+
+    fn open_tag(&mut self, mark: ShellMark) {
+        self.insertion.extend_from_slice(b"\x1b]8;;");
+        self.insertion.extend_from_slice(mark.uri().as_bytes());
+        self.insertion.push(0x07);
+    }
+
+    fn close_tag(&mut self) {
+        if self.pending.take().is_some() {
+            match &self.app_hyperlink {
+                Some(hyperlink) => self.insertion.extend_from_slice(hyperlink),
+                None => self.insertion.extend_from_slice(CLOSE_HYPERLINK),
+            }
+        }
+    }
+
+    fn mark(&mut self, kind: &[u8], params: &[&[u8]]) {
+        match kind {
+            b"A" => {
+                self.block += 1;
+                self.events
+                    .push(ShellIntegrationEvent::PromptStarted(self.block));
+                self.start_region(ShellRegion::Prompt);
+            }
+            b"B" => self.start_region(ShellRegion::Command),
+            b"C" => {
+                self.events
+                    .push(ShellIntegrationEvent::CommandExecuted(self.block));
+                self.start_region(ShellRegion::Output);
+            }
+            b"D" => {
+                let exit_code = params
+                    .first()
+                    .and_then(|code| str::from_utf8(code).ok()?.parse().ok());
+                self.close_tag();
+                self.events.push(ShellIntegrationEvent::CommandFinished {
+                    block: self.block,
+                    exit_code,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Tracks the hyperlink of the application, like alacritty's handling of `OSC 8`.
+    fn hyperlink(&mut self, params: &[&[u8]]) {
+        let [_, link_params, uri @ ..] = params else {
+            return;
+        };
+        let app_hyperlink = uri.iter().any(|part| !part.is_empty()).then(|| {
+            let mut sequence = b"\x1b]8;".to_vec();
+            sequence.extend_from_slice(link_params);
+            for part in uri {
+                sequence.push(b';');
+                sequence.extend_from_slice(part);
+            }
+            sequence.push(0x07);
+            sequence
+        });
+        self.app_hyperlink =
+            app_hyperlink.filter(|sequence| sequence.len() <= MAX_APP_HYPERLINK_LEN);
+        // Keep tagging the pending region's first cell over the application's hyperlink.
+        if let Some(region) = self.pending {
+            self.open_tag(ShellMark {
+                region,
+                block: self.block,
+            });
+        }
+    }
+}
+
+impl Perform for MarkPerformer {
+    fn print(&mut self, _: char) {
+        self.close_tag();
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], _: bool) {
+        match params {
+            [b"133", kind, params @ ..] => self.mark(kind, params),
+            [b"8", ..] => self.hyperlink(params),
+            // The path may contain the `;` separating the parameters.
+            [b"7", uri @ ..] => {
+                let uri = uri.join(&b';');
+                if let Some(directory) = str::from_utf8(&uri)
+                    .ok()
+                    .and_then(ReportedWorkingDirectory::from_uri)
+                {
+                    self.events
+                        .push(ShellIntegrationEvent::WorkingDirectoryChanged(directory));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn terminated(&self) -> bool {
+        !self.insertion.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(chunks: &[&[u8]]) -> (String, Vec<ShellIntegrationEvent>) {
+        let mut filter = ShellIntegrationFilter::new();
+        let mut output = Vec::new();
+        for chunk in chunks {
+            filter.filter(chunk, &mut output);
+        }
+        (String::from_utf8(output).unwrap(), filter.take_events())
+    }
+
+    #[test]
+    fn test_tags_first_cell_of_each_region() {
+        let (output, events) =
+            filter(&[b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07a b\r\n\x1b]133;D;1\x07"]);
+        assert_eq!(
+            output,
+            "\x1b]133;A\x07\x1b]8;;alacrterm-mark:prompt:1\x07$\x1b]8;;\x07 \
+             \x1b]133;B\x07\x1b]8;;alacrterm-mark:command:1\x07l\x1b]8;;\x07s\r\n\
+             \x1b]133;C\x07\x1b]8;;alacrterm-mark:output:1\x07a\x1b]8;;\x07 b\r\n\
+             \x1b]133;D;1\x07"
+        );
+        assert_eq!(
+            events,
+            [
+                ShellIntegrationEvent::PromptStarted(1),
+                ShellIntegrationEvent::CommandExecuted(1),
+                ShellIntegrationEvent::CommandFinished {
+                    block: 1,
+                    exit_code: Some(1)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sequences_split_across_reads() {
+        let (output, events) = filter(&[b"\x1b]13", b"3;A\x1b", b"\\\xe2", b"\x9d\xaf x"]);
+        assert_eq!(
+            output,
+            "\x1b]133;A\x1b\\\x1b]8;;alacrterm-mark:prompt:1\x07❯\x1b]8;;\x07 x"
+        );
+        assert_eq!(events, [ShellIntegrationEvent::PromptStarted(1)]);
+
+        // A command that printed nothing leaves no tag open.
+        let (output, _) = filter(&[b"\x1b]133;C\x07\x1b]133;D;0\x1b[0m"]);
+        assert_eq!(
+            output,
+            "\x1b]133;C\x07\x1b]8;;alacrterm-mark:output:0\x07\x1b]133;D;0\x1b\\\x1b]8;;\x07\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_restores_application_hyperlink() {
+        let (output, _) = filter(&[b"\x1b]133;C\x07\x1b]8;id=1;file:///a;b\x07a\x1b]8;;\x07"]);
+        assert_eq!(
+            output,
+            "\x1b]133;C\x07\x1b]8;;alacrterm-mark:output:0\x07\
+             \x1b]8;id=1;file:///a;b\x07\x1b]8;;alacrterm-mark:output:0\x07\
+             a\x1b]8;id=1;file:///a;b\x07\x1b]8;;\x07"
+        );
+    }
+
+    #[test]
+    fn test_output_of_max_input_len_fits() {
+        // A long application hyperlink restored after the tag of each of many prompts.
+        let mut input = b"\x1b]8;;".to_vec();
+        input.resize(MAX_APP_HYPERLINK_LEN - 1, b'a');
+        input.push(0x07);
+        for _ in 0..10_000 {
+            input.extend_from_slice(b"\x1b]133;A\x1b\\x");
+        }
+
+        for output_len in [4096, 0x10000, 0x100000] {
+            let len = ShellIntegrationFilter::max_input_len(output_len);
+            assert!(len > 0);
+            // Start after states that carry an insertion or an open tag over.
+            for start in [0, MAX_APP_HYPERLINK_LEN + 7, MAX_APP_HYPERLINK_LEN + 9] {
+                let mut filter = ShellIntegrationFilter::new();
+                filter.filter(&input[..start], &mut Vec::new());
+                let mut output = Vec::new();
+                filter.filter(&input[start..start + len], &mut output);
+                assert!(output.len() <= output_len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reports_working_directory() {
+        let (output, events) = filter(&[b"\x1b]7;file://localhost/home/me/a%20b;c\x07"]);
+        assert_eq!(output, "\x1b]7;file://localhost/home/me/a%20b;c\x07");
+        assert_eq!(
+            events,
+            [ShellIntegrationEvent::WorkingDirectoryChanged(
+                ReportedWorkingDirectory {
+                    host: None,
+                    path: PathBuf::from("/home/me/a b;c"),
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_working_directory_uri() {
+        assert_eq!(
+            ReportedWorkingDirectory::from_uri("file:///tmp"),
+            Some(ReportedWorkingDirectory {
+                host: None,
+                path: PathBuf::from("/tmp"),
+            })
+        );
+        assert_eq!(
+            ReportedWorkingDirectory::from_uri("file://build-server.example.com/srv/app"),
+            Some(ReportedWorkingDirectory {
+                host: Some("build-server.example.com".to_string()),
+                path: PathBuf::from("/srv/app"),
+            })
+        );
+        assert_eq!(ReportedWorkingDirectory::from_uri("file://host"), None);
+        assert_eq!(ReportedWorkingDirectory::from_uri("/tmp"), None);
+    }
+
+    #[test]
+    fn test_shell_mark_uri() {
+        let mark = ShellMark {
+            region: ShellRegion::Command,
+            block: 42,
+        };
+        assert_eq!(ShellMark::from_uri(&mark.uri()), Some(mark));
+        assert_eq!(ShellMark::from_uri("https://example.com"), None);
+        assert_eq!(ShellMark::from_uri("alacrterm-mark:prompt:x"), None);
+    }
+}
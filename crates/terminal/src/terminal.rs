@@ -2,7 +2,10 @@ mod mappings;
 mod default_colors;
 
 mod alacritty;
+pub mod color_scheme;
+mod modify_other_keys;
 mod pty_info;
+mod shell_integration;
 pub mod terminal_settings;
 
 use anyhow::{Result, bail};
@@ -29,7 +32,11 @@ use std::collections::{HashMap, VecDeque};
 use terminal_settings::{AlternateScroll, CursorShape as SettingsCursorShape, TerminalSettings};
 
 use urlencoding;
-use util::{paths::PathStyle, shell::Shell, truncate_and_trailoff};
+use util::{
+    paths::{PathExt, PathStyle},
+    shell::Shell,
+    truncate_and_trailoff,
+};
 
 use std::{
     borrow::Cow,
@@ -55,16 +62,25 @@ use gpui::{
 use crate::alacritty::{
     AlacrittyCell, AlacrittyGridIterator, AlacrittyHyperlink, AlacrittySearch, AlacrittyTerm,
     AlacrittyTermConfig, AlacrittyTermLock, HyperlinkMatch, PtySender, RegexSearches, apply_config,
-    clear_saved_screen, content_text, display_offset, display_only_term_config,
-    find_from_terminal_point, full_content_range, last_non_empty_lines, make_content, new_term,
-    open_pty, pty_options, pty_term_config, resize, screen_lines, scroll_display, scroll_to_point,
-    search_matches, selection_text, set_default_cursor_style, set_selection as set_term_selection,
-    spawn_event_loop, toggle_vi_mode as toggle_term_vi_mode, total_lines,
+    clear_saved_screen, command_output_range, content_text, display_offset,
+    display_only_term_config, find_from_terminal_point, find_shell_mark, full_content_range,
+    last_non_empty_lines, last_shell_mark, make_content, new_term, open_pty, pty_options,
+    pty_term_config, resize, screen_lines, scroll_display, scroll_to_point, search_matches,
+    selection_text, set_default_cursor_style, set_selection as set_term_selection, shell_mark_at,
+    spawn_event_loop, text_of_selection, toggle_vi_mode as toggle_term_vi_mode, total_lines,
     update_selection as update_term_selection, update_selection_to_vi_cursor,
-    update_vi_cursor_for_scroll, vi_goto_point, vi_motion,
+    update_vi_cursor_for_scroll, vi_cursor_point, vi_goto_point, vi_motion,
 };
+
 use crate::mappings::colors::to_vte_rgb;
-use crate::mappings::keys::to_esc_str;
+use crate::mappings::keys::{
+    KeyEventType, to_esc_str, to_kitty_esc_str, to_kitty_modifier_esc_strs,
+};
+use crate::shell_integration::ShellIntegrationEvent;
+
+pub use crate::shell_integration::{
+    CommandBlock, ReportedWorkingDirectory, ShellMark, ShellRegion,
+};
 
 #[derive(Clone, Copy, Debug)]
 enum Scroll {
@@ -344,6 +360,13 @@ impl Modes {
     pub const MOUSE_DRAG: Self = Self(1 << 14);
     pub const MOUSE_MOTION: Self = Self(1 << 15);
     pub const VI: Self = Self(1 << 16);
+    pub const DISAMBIGUATE_ESC_CODES: Self = Self(1 << 17);
+    pub const REPORT_EVENT_TYPES: Self = Self(1 << 18);
+    pub const REPORT_ALTERNATE_KEYS: Self = Self(1 << 19);
+    pub const REPORT_ALL_KEYS_AS_ESC: Self = Self(1 << 20);
+    pub const REPORT_ASSOCIATED_TEXT: Self = Self(1 << 21);
+    /// xterm's modifyOtherKeys mode 2, which alacritty does not track, see [`Terminal`].
+    pub const MODIFY_OTHER_KEYS: Self = Self(1 << 22);
     pub const MOUSE_MODE: Self =
         Self(Self::MOUSE_REPORT_CLICK.0 | Self::MOUSE_DRAG.0 | Self::MOUSE_MOTION.0);
 
@@ -597,6 +620,17 @@ actions!(
         ToggleViMode,
         /// Selects all text in the terminal.
         SelectAll,
+        /// Scrolls to the previous shell prompt.
+        JumpToPreviousPrompt,
+        /// Scrolls to the next shell prompt.
+        JumpToNextPrompt,
+        /// Selects the output of the command under the vi mode cursor or the mouse, or else of
+        /// the last command.
+        SelectCommandOutput,
+        /// Copies the output of the last command to the clipboard.
+        CopyLastCommandOutput,
+        /// Saves the output of the last command to a file.
+        SaveLastCommandOutput,
     ]
 );
 
@@ -635,6 +669,8 @@ pub struct PathLikeTarget {
     pub maybe_path: String,
     /// Current working directory of the terminal
     pub terminal_dir: Option<PathBuf>,
+    /// The host `terminal_dir` is on, if it is not this machine.
+    pub terminal_host: Option<String>,
 }
 
 /// A string inside terminal, potentially useful as a URI that can be opened.
@@ -664,6 +700,9 @@ enum InternalEvent {
     ToggleViMode,
     ViMotion(ViMotion),
     MoveViCursorToPoint(Point),
+    JumpToPreviousPrompt,
+    JumpToNextPrompt,
+    SelectCommandOutput(Option<GpuiPoint<Pixels>>),
 }
 
 type ClipboardFormatter = Arc<dyn Fn(&str) -> String + Sync + Send + 'static>;
@@ -709,6 +748,8 @@ impl fmt::Debug for TerminalBackendEvent {
 
 enum PtyEvent {
     Event(TerminalBackendEvent),
+    ShellIntegration(ShellIntegrationEvent),
+    ModifyOtherKeysChanged(bool),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -838,6 +879,8 @@ impl Display for TerminalError {
 
 // https://github.com/alacritty/alacritty/blob/cb3a79dbf6472740daca8440d5166c1d4af5029e/extra/man/alacritty.5.scd?plain=1#L207-L213
 const DEFAULT_SCROLL_HISTORY_LINES: usize = 10_000;
+/// How many command blocks are remembered, the oldest ones have usually left the scrollback.
+const MAX_COMMAND_BLOCKS: usize = 1_000;
 pub const MAX_SCROLL_HISTORY_LINES: usize = 100_000;
 
 pub struct TerminalBuilder {
@@ -906,6 +949,7 @@ impl TerminalBuilder {
             selection_phase: SelectionPhase::Ended,
             hyperlink_regex_searches: RegexSearches::default(),
             vi_mode_enabled: false,
+            modifiers: Modifiers::default(),
             is_remote_terminal: false,
             last_mouse_move_time: Instant::now(),
             last_hyperlink_search_position: None,
@@ -924,6 +968,10 @@ impl TerminalBuilder {
                 window_id,
             },
             child_exited: None,
+            command_blocks: VecDeque::new(),
+            reported_working_directory: None,
+            working_directory_reporter: None,
+            modify_other_keys: false,
             keyboard_input_sent: false,
             event_loop_task: Task::ready(Ok(())),
             background_executor: background_executor.clone(),
@@ -1110,6 +1158,7 @@ impl TerminalBuilder {
                     path_hyperlink_timeout_ms,
                 ),
                 vi_mode_enabled: false,
+                modifiers: Modifiers::default(),
                 is_remote_terminal,
                 last_mouse_move_time: Instant::now(),
                 last_hyperlink_search_position: None,
@@ -1128,6 +1177,10 @@ impl TerminalBuilder {
                     window_id,
                 },
                 child_exited: None,
+                command_blocks: VecDeque::new(),
+                reported_working_directory: None,
+                working_directory_reporter: None,
+                modify_other_keys: false,
                 keyboard_input_sent: false,
                 event_loop_task: Task::ready(Ok(())),
                 background_executor,
@@ -1165,6 +1218,12 @@ impl TerminalBuilder {
         cx.background_spawn(fut)
     }
 
+    /// Has the terminals cloned from this one run `shell` instead of the shell this one runs.
+    pub fn with_template_shell(mut self, shell: Shell) -> Self {
+        self.terminal.template.shell = shell;
+        self
+    }
+
     pub fn subscribe(mut self, cx: &Context<Terminal>) -> Terminal {
         //Event loop
         self.terminal.event_loop_task = cx.spawn(async move |terminal, cx| {
@@ -1277,6 +1336,9 @@ pub struct Terminal {
     selection_phase: SelectionPhase,
     hyperlink_regex_searches: RegexSearches,
     vi_mode_enabled: bool,
+    /// The modifiers held as of the last modifiers change while focused, to report the modifier
+    /// keys pressed and released with the kitty keyboard protocol.
+    modifiers: Modifiers,
     is_remote_terminal: bool,
     last_mouse_move_time: Instant,
     last_hyperlink_search_position: Option<GpuiPoint<Pixels>>,
@@ -1286,6 +1348,15 @@ pub struct Terminal {
     template: CopyTemplate,
     activation_script: Vec<String>,
     child_exited: Option<ExitStatus>,
+    /// The command blocks delimited by shell integration marks, oldest first.
+    command_blocks: VecDeque<CommandBlock>,
+    /// The working directory the shell last reported with OSC 7.
+    reported_working_directory: Option<ReportedWorkingDirectory>,
+    /// The foreground process of the PTY when the working directory was reported. Once another
+    /// one takes over, like the local shell after an ssh session ends, the report is stale.
+    working_directory_reporter: Option<sysinfo::Pid>,
+    /// Whether the application set modifyOtherKeys to 2, added to the modes of the content.
+    modify_other_keys: bool,
     keyboard_input_sent: bool,
     event_loop_task: Task<Result<(), anyhow::Error>>,
     background_executor: BackgroundExecutor,
@@ -1311,6 +1382,41 @@ impl Terminal {
     fn process_pty_event(&mut self, event: PtyEvent, cx: &mut Context<Self>) {
         match event {
             PtyEvent::Event(event) => self.process_event(event, cx),
+            PtyEvent::ShellIntegration(event) => self.process_shell_integration_event(event, cx),
+            PtyEvent::ModifyOtherKeysChanged(enabled) => self.modify_other_keys = enabled,
+        }
+    }
+
+    fn process_shell_integration_event(
+        &mut self,
+        event: ShellIntegrationEvent,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            ShellIntegrationEvent::PromptStarted(id) => {
+                if self.command_blocks.len() == MAX_COMMAND_BLOCKS {
+                    self.command_blocks.pop_front();
+                }
+                self.command_blocks.push_back(CommandBlock::new(id));
+            }
+            ShellIntegrationEvent::CommandExecuted(id) => {
+                if let Some(block) = self.command_block_mut(id) {
+                    block.executed_at = Some(Instant::now());
+                }
+            }
+            ShellIntegrationEvent::CommandFinished { block, exit_code } => {
+                if let Some(block) = self.command_block_mut(block) {
+                    block.finished_at = Some(Instant::now());
+                    block.exit_code = exit_code;
+                }
+            }
+            ShellIntegrationEvent::WorkingDirectoryChanged(directory) => {
+                self.working_directory_reporter = self.pid();
+                if self.reported_working_directory.as_ref() != Some(&directory) {
+                    self.reported_working_directory = Some(directory);
+                    cx.emit(Event::TitleChanged);
+                }
+            }
         }
     }
 
@@ -1368,6 +1474,12 @@ impl Terminal {
             TerminalBackendEvent::Wakeup => {
                 cx.emit(Event::Wakeup);
 
+                if self.reported_working_directory.is_some()
+                    && self.pid() != self.working_directory_reporter
+                {
+                    self.reported_working_directory = None;
+                    cx.emit(Event::TitleChanged);
+                }
                 if let TerminalType::Pty { info, .. } = &self.terminal_type {
                     info.emit_title_changed_if_changed(cx);
                 }
@@ -1381,9 +1493,7 @@ impl Terminal {
                 // Instead of locking, we could store the colors in `self.last_content`. But then
                 // we might respond with out of date value if a "set color" sequence is immediately
                 // followed by a color request sequence.
-
-                let color = self.term.lock().colors()[index]
-                    .unwrap_or_else(|| to_vte_rgb(get_color_at_index(index, &ThemeColors::dark())));
+                let color = requested_color(&self.term.lock(), index, cx.terminal_colors());
                 self.write_to_pty(format(color).into_bytes());
             }
             TerminalBackendEvent::ChildExit(exit_status) => {
@@ -1415,6 +1525,8 @@ impl Terminal {
                 }
 
                 resize(term, new_bounds);
+                // The size can be part of the title.
+                cx.emit(Event::TitleChanged);
                 // If there are matches we need to emit a wake up event to
                 // invalidate the matches and recalculate their locations
                 // in the new terminal layout
@@ -1536,6 +1648,62 @@ impl Terminal {
             InternalEvent::ProcessHyperlink(hyperlink, open) => {
                 self.process_hyperlink(hyperlink.clone(), *open, cx);
             }
+            InternalEvent::JumpToPreviousPrompt => {
+                let top = -(display_offset(term) as i32);
+                let first_line = full_content_range(term).start().line;
+                if let Some((point, _)) = find_shell_mark(term, (first_line..top).rev(), |mark| {
+                    mark.region == ShellRegion::Prompt
+                }) {
+                    trace!("Jumping to previous prompt: point={point:?}");
+                    // Prompts above the viewport are scrolled to its top.
+                    scroll_to_point(term, point);
+                    self.refresh_hovered_word(window);
+                }
+            }
+            InternalEvent::JumpToNextPrompt => {
+                let top = -(display_offset(term) as i32);
+                let last_line = full_content_range(term).end().line;
+                match find_shell_mark(term, top + 1..=last_line, |mark| {
+                    mark.region == ShellRegion::Prompt
+                }) {
+                    Some((point, _)) => {
+                        trace!("Jumping to next prompt: point={point:?}");
+                        // Scroll the line a screen below the prompt into view, bringing the prompt
+                        // to the top of the viewport as far as the scrollback allows.
+                        let screen_lines = screen_lines(term) as i32;
+                        scroll_to_point(term, Point::new(point.line + screen_lines - 1, 0));
+                    }
+                    None => scroll_display(term, Scroll::Bottom),
+                }
+                self.refresh_hovered_word(window);
+            }
+            InternalEvent::SelectCommandOutput(position) => {
+                let point = if self.vi_mode_enabled {
+                    Some(vi_cursor_point(term))
+                } else {
+                    position.map(|position| {
+                        grid_point(
+                            position,
+                            self.last_content.terminal_bounds,
+                            display_offset(term),
+                        )
+                    })
+                };
+                let mark = match point {
+                    // Without shell integration there are no marks to find.
+                    Some(_) if self.command_blocks.is_empty() => None,
+                    Some(point) => shell_mark_at(term, point),
+                    None => self
+                        .last_command_block()
+                        .and_then(|block| last_shell_mark(term, block)),
+                };
+                if let Some(range) =
+                    mark.and_then(|(point, mark)| command_output_range(term, point, mark))
+                {
+                    trace!("Selecting command output: range={range:?}");
+                    self.set_selection(Some(Selection::simple_range(range)));
+                }
+            }
         }
     }
 
@@ -1556,6 +1724,7 @@ impl Terminal {
                 MaybeNavigationTarget::PathLike(PathLikeTarget {
                     maybe_path: decoded_path,
                     terminal_dir: self.working_directory(),
+                    terminal_host: self.working_directory_host().map(str::to_string),
                 })
             } else {
                 MaybeNavigationTarget::Url(maybe_url_or_path.clone())
@@ -1564,6 +1733,7 @@ impl Terminal {
             MaybeNavigationTarget::PathLike(PathLikeTarget {
                 maybe_path: maybe_url_or_path.clone(),
                 terminal_dir: self.working_directory(),
+                terminal_host: self.working_directory_host().map(str::to_string),
             })
         };
 
@@ -1626,6 +1796,36 @@ impl Terminal {
     pub fn set_cursor_shape(&mut self, cursor_shape: SettingsCursorShape) {
         set_default_cursor_style(&mut self.term_config, cursor_shape);
         apply_config(&self.term, &self.term_config);
+        self.template.cursor_shape = cursor_shape;
+    }
+
+    /// Changes how many lines of scrollback the terminal keeps, trimming it if it shrinks.
+    pub fn set_max_scroll_history_lines(&mut self, max_scroll_history_lines: Option<usize>) {
+        self.template.max_scroll_history_lines = max_scroll_history_lines;
+        let scrolling_history = max_scroll_history_lines
+            .unwrap_or(DEFAULT_SCROLL_HISTORY_LINES)
+            .min(MAX_SCROLL_HISTORY_LINES);
+        if self.term_config.scrolling_history != scrolling_history {
+            self.term_config.scrolling_history = scrolling_history;
+            apply_config(&self.term, &self.term_config);
+        }
+    }
+
+    /// Replaces the regexes used to detect path hyperlinks.
+    pub fn set_path_hyperlink_regexes(
+        &mut self,
+        path_hyperlink_regexes: Vec<String>,
+        path_hyperlink_timeout_ms: u64,
+    ) {
+        if self.template.path_hyperlink_regexes == path_hyperlink_regexes
+            && self.template.path_hyperlink_timeout_ms == path_hyperlink_timeout_ms
+        {
+            return;
+        }
+        self.hyperlink_regex_searches =
+            RegexSearches::new(&path_hyperlink_regexes, path_hyperlink_timeout_ms);
+        self.template.path_hyperlink_regexes = path_hyperlink_regexes;
+        self.template.path_hyperlink_timeout_ms = path_hyperlink_timeout_ms;
     }
 
     pub fn write_output(&mut self, bytes: &[u8], cx: &mut Context<Self>) {
@@ -1748,6 +1948,57 @@ impl Terminal {
         self.events.push_back(InternalEvent::Scroll(Scroll::Bottom));
     }
 
+    pub fn jump_to_previous_prompt(&mut self) {
+        self.events.push_back(InternalEvent::JumpToPreviousPrompt);
+    }
+
+    pub fn jump_to_next_prompt(&mut self) {
+        self.events.push_back(InternalEvent::JumpToNextPrompt);
+    }
+
+    /// Selects the output of the command under the vi mode cursor, or at `position` (in window
+    /// coordinates) when it is over the terminal, or else of the last command.
+    pub fn select_command_output(&mut self, position: GpuiPoint<Pixels>) {
+        let bounds = self.last_content.terminal_bounds.bounds;
+        let position = bounds.contains(&position).then(|| position - bounds.origin);
+        self.events
+            .push_back(InternalEvent::SelectCommandOutput(position));
+    }
+
+    /// Returns the output of the last command that ran, as far as it is still in the scrollback.
+    pub fn last_command_output(&self) -> Option<String> {
+        let block = self.last_command_block()?;
+        let mut term = self.term.lock();
+        let (point, mark) = last_shell_mark(&term, block)?;
+        let range = command_output_range(&term, point, mark)?;
+        text_of_selection(&mut term, &Selection::simple_range(range))
+    }
+
+    fn last_command_block(&self) -> Option<u64> {
+        self.command_blocks
+            .iter()
+            .rev()
+            .find(|block| block.executed_at.is_some())
+            .map(|block| block.id)
+    }
+
+    /// Returns the command block of the `id`th prompt, unless it was forgotten.
+    pub fn command_block(&self, id: u64) -> Option<&CommandBlock> {
+        let index = self
+            .command_blocks
+            .binary_search_by_key(&id, |block| block.id)
+            .ok()?;
+        self.command_blocks.get(index)
+    }
+
+    fn command_block_mut(&mut self, id: u64) -> Option<&mut CommandBlock> {
+        let index = self
+            .command_blocks
+            .binary_search_by_key(&id, |block| block.id)
+            .ok()?;
+        self.command_blocks.get_mut(index)
+    }
+
     pub fn scrolled_to_top(&self) -> bool {
         self.last_content.scrolled_to_top
     }
@@ -1906,13 +2157,51 @@ impl Terminal {
     }
 
     pub fn try_keystroke(&mut self, keystroke: &Keystroke, option_as_meta: bool) -> bool {
+        self.try_key_press(keystroke, KeyEventType::Press, option_as_meta)
+    }
+
+    /// Like [`Self::try_keystroke`], for the repeats of a held key, which applications can tell
+    /// apart from presses with the kitty keyboard protocol.
+    pub fn try_key_repeat(&mut self, keystroke: &Keystroke, option_as_meta: bool) -> bool {
+        self.try_key_press(keystroke, KeyEventType::Repeat, option_as_meta)
+    }
+
+    /// Reports the release of a key, when the application asked for it with the kitty keyboard
+    /// protocol.
+    pub fn try_key_release(&mut self, keystroke: &Keystroke, option_as_meta: bool) -> bool {
+        if self.vi_mode_enabled {
+            return false;
+        }
+
+        let mode = self.last_content.mode;
+        match to_kitty_esc_str(keystroke, mode, KeyEventType::Release, option_as_meta) {
+            Some(esc) => {
+                self.write_to_pty(esc.into_bytes());
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn try_key_press(
+        &mut self,
+        keystroke: &Keystroke,
+        event_type: KeyEventType,
+        option_as_meta: bool,
+    ) -> bool {
         if self.vi_mode_enabled {
             self.vi_motion(keystroke);
             return true;
         }
 
         // Keep default terminal behavior
-        let esc = to_esc_str(keystroke, self.last_content.mode, option_as_meta);
+        let mode = self.last_content.mode;
+        let esc = match event_type {
+            KeyEventType::Press => to_esc_str(keystroke, mode, option_as_meta),
+            _ => to_kitty_esc_str(keystroke, mode, event_type, option_as_meta)
+                .map(Cow::Owned)
+                .or_else(|| to_esc_str(keystroke, mode, option_as_meta)),
+        };
         if let Some(esc) = esc {
             match esc {
                 Cow::Borrowed(string) => self.input(string.as_bytes()),
@@ -1924,6 +2213,18 @@ impl Terminal {
         }
     }
 
+    /// Reports the modifiers pressed or released on their own to the application, if it asked
+    /// for them with the kitty keyboard protocol. Only the focused terminal is meant to get them.
+    pub fn report_modifiers_change(&mut self, modifiers: &Modifiers) {
+        let previous_modifiers = std::mem::replace(&mut self.modifiers, *modifiers);
+        if !self.vi_mode_enabled {
+            let mode = self.last_content.mode;
+            for esc in to_kitty_modifier_esc_strs(&previous_modifiers, modifiers, mode) {
+                self.write_to_pty(esc.into_bytes());
+            }
+        }
+    }
+
     pub fn try_modifiers_change(
         &mut self,
         modifiers: &Modifiers,
@@ -1962,6 +2263,9 @@ impl Terminal {
         }
 
         self.last_content = make_content(&terminal, &self.last_content);
+        if self.modify_other_keys {
+            self.last_content.mode.insert(Modes::MODIFY_OTHER_KEYS);
+        }
     }
 
     pub fn with_renderable_cells<R>(&self, f: impl for<'a> FnOnce(RenderableCells<'a>) -> R) -> R {
@@ -2369,8 +2673,12 @@ impl Terminal {
         })
     }
 
+    /// Returns the working directory the shell last reported, falling back to the one of the
+    /// process connected to the PTY. See [`Self::working_directory_host`] for where it is.
     pub fn working_directory(&self) -> Option<PathBuf> {
-        if self.is_remote_terminal {
+        if let Some(reported) = &self.reported_working_directory {
+            Some(reported.path.clone())
+        } else if self.is_remote_terminal {
             // We can't yet reliably detect the working directory of a shell on the
             // SSH host. Until we can do that, it doesn't make sense to display
             // the working directory on the client and persist that.
@@ -2380,6 +2688,24 @@ impl Terminal {
         }
     }
 
+    /// The host the shell reported its working directory on, if it is not this machine.
+    pub fn working_directory_host(&self) -> Option<&str> {
+        self.reported_working_directory
+            .as_ref()
+            .and_then(|reported| reported.host.as_deref())
+    }
+
+    /// The working directory to start terminals cloned from or restored in place of this one in,
+    /// which has to be on this machine.
+    pub fn local_working_directory(&self) -> Option<PathBuf> {
+        if self.working_directory_host().is_some() {
+            // The shell runs elsewhere, the local process connected to the PTY started it.
+            self.client_side_working_directory()
+        } else {
+            self.working_directory()
+        }
+    }
+
     /// Normalizes the command name of the foreground process, if one is known.
     pub fn foreground_process_command_name(&self) -> Option<String> {
         match &self.terminal_type {
@@ -2420,11 +2746,18 @@ impl Terminal {
                     .read()
                     .as_ref()
                     .map(|fpi| {
-                        let process_file = fpi
-                            .cwd
+                        let cwd = self
+                            .reported_working_directory
+                            .as_ref()
+                            .map_or(&fpi.cwd, |reported| &reported.path);
+                        let cwd_name = cwd
                             .file_name()
                             .map(|name| name.to_string_lossy().into_owned())
                             .unwrap_or_default();
+                        let process_file = match self.working_directory_host() {
+                            Some(host) => format!("{host}:{cwd_name}"),
+                            None => cwd_name,
+                        };
 
                         let argv = fpi.argv.as_slice();
                         let process_name = format!(
@@ -2451,6 +2784,32 @@ impl Terminal {
             })
     }
 
+    /// Expands the placeholders of a `title` setting template for this terminal.
+    pub fn title_from_template(&self, template: &str) -> String {
+        let bounds = &self.last_content.terminal_bounds;
+        expand_title_template(template, |placeholder| match placeholder {
+            "title" => Some(self.title(false)),
+            "osc_title" => Some(self.breadcrumb_text.clone()),
+            "process" => Some(self.foreground_process_command_name().unwrap_or_default()),
+            "cwd" => Some(
+                self.working_directory()
+                    .map(|cwd| match self.working_directory_host() {
+                        Some(_) => cwd.to_string_lossy().into_owned(),
+                        None => cwd.compact().to_string_lossy().into_owned(),
+                    })
+                    .unwrap_or_default(),
+            ),
+            "host" => Some(
+                self.working_directory_host()
+                    .unwrap_or_default()
+                    .to_string(),
+            ),
+            "cols" => Some(bounds.num_columns().to_string()),
+            "rows" => Some(bounds.num_lines().to_string()),
+            _ => None,
+        })
+    }
+
     pub fn pid(&self) -> Option<sysinfo::Pid> {
         match &self.terminal_type {
             TerminalType::Pty { info, .. } => info.pid(),
@@ -2491,8 +2850,13 @@ impl Terminal {
         self.vi_mode_enabled
     }
 
+    /// The exit status of the terminal's process, if it has exited.
+    pub fn child_exited(&self) -> Option<ExitStatus> {
+        self.child_exited
+    }
+
     pub fn clone_builder(&self, cx: &App, cwd: Option<PathBuf>) -> Task<Result<TerminalBuilder>> {
-        let working_directory = self.working_directory().or_else(|| cwd);
+        let working_directory = self.local_working_directory().or_else(|| cwd);
         TerminalBuilder::new(
             working_directory,
             self.template.shell.clone(),
@@ -2615,6 +2979,13 @@ fn content_index_for_mouse(pos: GpuiPoint<Pixels>, terminal_bounds: &TerminalBou
 /// Converts an 8 bit ANSI color to its GPUI equivalent.
 /// Accepts `usize` for compatibility with the `alacritty::Colors` interface,
 /// Other than that use case, should only be called with values in the `[0,255]` range
+/// The color to answer a request for the color at `index` with. Colors set through OSC 4/10/11/12
+/// take precedence; the rest, including colors reset through OSC 104/110/111/112, come from
+/// `colors`, the active theme's.
+fn requested_color(term: &AlacrittyTerm, index: usize, colors: &ThemeColors) -> Rgb {
+    term.colors()[index].unwrap_or_else(|| to_vte_rgb(get_color_at_index(index, colors)))
+}
+
 pub fn get_color_at_index(index: usize, colors: &ThemeColors) -> Hsla {
     // let colors = theme.colors();
 
@@ -2701,10 +3072,89 @@ pub fn rgba_color(r: u8, g: u8, b: u8) -> Hsla {
     }
     .into()
 }
+/// Replaces each `{name}` in `template` with the value `placeholder` returns for it, keeping
+/// unknown placeholders as they are.
+fn expand_title_template(template: &str, placeholder: impl Fn(&str) -> Option<String>) -> String {
+    let mut title = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        title.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest
+            .find('}')
+            .and_then(|end| Some((placeholder(&rest[1..end])?, end)));
+        match value {
+            Some((value, end)) => {
+                title.push_str(&value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                title.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    title.push_str(rest);
+    title
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[gpui::test]
+    fn test_color_requests_answer_with_the_active_theme(cx: &mut gpui::TestAppContext) {
+        let defaults = ThemeColors::dark();
+        let mut theme = defaults.clone();
+        theme.terminal_foreground = gpui::rgb(0x336699).into();
+        theme.terminal_background = gpui::rgb(0xccbb99).into();
+        theme.terminal_ansi_blue = gpui::rgb(0x0066ff).into();
+
+        let (events_tx, mut events_rx) = futures::channel::mpsc::unbounded();
+        let config = display_only_term_config(100, SettingsCursorShape::Block);
+        let term = new_term(
+            &config,
+            TerminalBounds::default(),
+            events_tx,
+            AlternateScroll::On,
+        );
+        let mut term = term.lock();
+        let mut answer = |input: &[u8], cx: &App| {
+            Processor::<StdSyncHandler>::new().advance(&mut *term, input);
+            std::iter::from_fn(|| events_rx.next().now_or_never().flatten())
+                .filter_map(|event| match event {
+                    PtyEvent::Event(TerminalBackendEvent::ColorRequest(index, format)) => {
+                        Some(format(requested_color(&term, index, cx.terminal_colors())))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let reply = |prefix: &str, color: Hsla| {
+            let Rgb { r, g, b } = to_vte_rgb(color);
+            format!("\x1b]{prefix};rgb:{r:02x}{r:02x}/{g:02x}{g:02x}/{b:02x}{b:02x}\x07")
+        };
+        cx.update(|cx| cx.set_global(theme.clone()));
+
+        // The foreground, background and palette colors come from the theme...
+        let replies = cx.update(|cx| answer(b"\x1b]10;?\x07\x1b]11;?\x07\x1b]4;4;?\x07", cx));
+        assert_eq!(
+            replies,
+            [
+                reply("10", theme.terminal_foreground),
+                reply("11", theme.terminal_background),
+                reply("4;4", theme.terminal_ansi_blue),
+            ]
+        );
+        assert_ne!(replies[0], reply("10", defaults.terminal_foreground));
+        assert_ne!(replies[1], reply("11", defaults.terminal_background));
+        assert_ne!(replies[2], reply("4;4", defaults.terminal_ansi_blue));
+
+        // ...unless the program set them.
+        let replies = cx.update(|cx| answer(b"\x1b]11;rgb:12/34/56\x07\x1b]11;?\x07", cx));
+        assert_eq!(replies, ["\x1b]11;rgb:1212/3434/5656\x07"]);
+    }
+
     #[test]
     fn test_normalize_path_command_name() {
         assert_eq!(normalize_path_command_name("claude"), Some("claude".into()));
@@ -2752,4 +3202,25 @@ mod tests {
             Some("customer-data-export".to_string())
         );
     }
+
+    #[test]
+    fn test_expand_title_template() {
+        let placeholder = |name: &str| match name {
+            "process" => Some("vim".to_string()),
+            "cwd" => Some("~/src".to_string()),
+            "cols" => Some("80".to_string()),
+            "rows" => Some("24".to_string()),
+            "osc_title" => Some(String::new()),
+            _ => None,
+        };
+        assert_eq!(
+            expand_title_template("{process} in {cwd} ({cols}x{rows})", placeholder),
+            "vim in ~/src (80x24)"
+        );
+        assert_eq!(expand_title_template("{osc_title}", placeholder), "");
+        assert_eq!(
+            expand_title_template("{unknown} {cwd} {", placeholder),
+            "{unknown} ~/src {"
+        );
+    }
 }
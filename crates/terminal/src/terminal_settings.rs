@@ -6,11 +6,11 @@ use std::collections::HashMap;
 pub use settings::AlternateScroll;
 
 use settings::FontFamilyName;
-use settings::Shell;
 use settings::{
     IntoGpui, PathHyperlinkRegex, ShowScrollbar, TerminalBell, TerminalBlink, TerminalDockPosition,
-    TerminalLineHeight, VenvSettings, WorkingDirectory,
+    TerminalExitBehavior, TerminalLineHeight, VenvSettings, WorkingDirectory,
 };
+use util::shell::Shell;
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct Toolbar {
@@ -49,6 +49,8 @@ pub struct TerminalSettings {
     pub path_hyperlink_timeout_ms: u64,
     pub show_count_badge: bool,
     pub bell: TerminalBell,
+    pub on_exit: TerminalExitBehavior,
+    pub title: String,
 }
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct ScrollbarSettings {
@@ -77,13 +79,16 @@ fn settings_shell_to_shell(shell: settings::Shell) -> Shell {
 // 解析并合并全局用户配置与项目本地配置
 impl settings::Settings for TerminalSettings {
     fn from_settings(content: &settings::SettingsContent) -> Self {
-        let user_content = content.terminal.clone().unwrap();
+        // Missing values fall back to the ones `default.json` sets, should a merge leave one out.
+        let user_content = content.terminal.clone().unwrap_or_default();
         // Note: we allow a subset of "terminal" settings in the project files.
-        let project_content = user_content.project.clone();
-        // project_content.merge_from_option(content.project.terminal.as_ref());
+        let mut project_content = user_content.project.clone();
+        project_content.merge_from_option(content.project.terminal.as_ref());
         TerminalSettings {
-            shell: settings_shell_to_shell(project_content.shell.unwrap()),
-            working_directory: project_content.working_directory.unwrap(),
+            shell: settings_shell_to_shell(project_content.shell.unwrap_or_default()),
+            working_directory: project_content
+                .working_directory
+                .unwrap_or(WorkingDirectory::CurrentProjectDirectory),
             font_size: user_content.font_size.map(|s| s.into_gpui()),
             font_family: user_content.font_family,
             font_fallbacks: user_content.font_fallbacks.map(|fallbacks| {
@@ -96,41 +101,50 @@ impl settings::Settings for TerminalSettings {
             }),
             font_features: user_content.font_features.map(|f| f.into_gpui()),
             font_weight: user_content.font_weight.map(|w| w.into_gpui()),
-            line_height: user_content.line_height.unwrap(),
-            env: project_content.env.unwrap(),
-            cursor_shape: user_content.cursor_shape.unwrap().into(),
-            blinking: user_content.blinking.unwrap(),
-            alternate_scroll: user_content.alternate_scroll.unwrap(),
-            option_as_meta: user_content.option_as_meta.unwrap(),
-            copy_on_select: user_content.copy_on_select.unwrap(),
-            keep_selection_on_copy: user_content.keep_selection_on_copy.unwrap(),
-            button: user_content.button.unwrap(),
-            dock: user_content.dock.unwrap(),
-            default_width: px(user_content.default_width.unwrap()),
-            default_height: px(user_content.default_height.unwrap()),
-            flexible: user_content.flexible.unwrap(),
-            detect_venv: project_content.detect_venv.unwrap(),
-            scroll_multiplier: user_content.scroll_multiplier.unwrap(),
+            line_height: user_content
+                .line_height
+                .unwrap_or(TerminalLineHeight::Standard),
+            env: project_content.env.unwrap_or_default(),
+            cursor_shape: user_content.cursor_shape.unwrap_or_default().into(),
+            blinking: user_content
+                .blinking
+                .unwrap_or(TerminalBlink::TerminalControlled),
+            alternate_scroll: user_content.alternate_scroll.unwrap_or(AlternateScroll::On),
+            option_as_meta: user_content.option_as_meta.unwrap_or(false),
+            copy_on_select: user_content.copy_on_select.unwrap_or(false),
+            keep_selection_on_copy: user_content.keep_selection_on_copy.unwrap_or(true),
+            button: user_content.button.unwrap_or(true),
+            dock: user_content.dock.unwrap_or(TerminalDockPosition::Bottom),
+            default_width: px(user_content.default_width.unwrap_or(640.)),
+            default_height: px(user_content.default_height.unwrap_or(320.)),
+            flexible: user_content.flexible.unwrap_or(true),
+            detect_venv: project_content.detect_venv.unwrap_or_default(),
+            scroll_multiplier: user_content.scroll_multiplier.unwrap_or(1.),
             max_scroll_history_lines: user_content.max_scroll_history_lines,
             toolbar: Toolbar {
-                breadcrumbs: user_content.toolbar.unwrap().breadcrumbs.unwrap(),
+                breadcrumbs: user_content
+                    .toolbar
+                    .and_then(|toolbar| toolbar.breadcrumbs)
+                    .unwrap_or(false),
             },
             scrollbar: ScrollbarSettings {
-                show: user_content.scrollbar.unwrap().show,
+                show: user_content.scrollbar.and_then(|scrollbar| scrollbar.show),
             },
-            minimum_contrast: user_content.minimum_contrast.unwrap(),
+            minimum_contrast: user_content.minimum_contrast.unwrap_or(45.),
             path_hyperlink_regexes: project_content
                 .path_hyperlink_regexes
-                .unwrap()
+                .unwrap_or_default()
                 .into_iter()
                 .map(|regex| match regex {
                     PathHyperlinkRegex::SingleLine(regex) => regex,
                     PathHyperlinkRegex::MultiLine(regex) => regex.join("\n"),
                 })
                 .collect(),
-            path_hyperlink_timeout_ms: project_content.path_hyperlink_timeout_ms.unwrap(),
-            show_count_badge: user_content.show_count_badge.unwrap(),
-            bell: user_content.bell.unwrap(),
+            path_hyperlink_timeout_ms: project_content.path_hyperlink_timeout_ms.unwrap_or(1),
+            show_count_badge: user_content.show_count_badge.unwrap_or(false),
+            bell: user_content.bell.unwrap_or(TerminalBell::Off),
+            on_exit: user_content.on_exit.unwrap_or_default(),
+            title: user_content.title.unwrap_or_else(|| "{title}".to_string()),
         }
     }
 }
@@ -159,3 +173,18 @@ impl From<settings::CursorShapeContent> for CursorShape {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use settings::Settings as _;
+
+    use super::*;
+
+    #[test]
+    fn test_missing_values_fall_back_to_defaults() {
+        let settings = TerminalSettings::from_settings(&settings::SettingsContent::default());
+        assert_eq!(settings.shell, Shell::System);
+        assert_eq!(settings.alternate_scroll, AlternateScroll::On);
+        assert_eq!(settings.title, "{title}");
+    }
+}
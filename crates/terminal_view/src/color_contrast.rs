@@ -0,0 +1,183 @@
+//! Contrast adjustment for terminal text, based on the Accessible Perceptual Contrast Algorithm.
+//!
+//! See <https://github.com/Myndex/apca-w3> for the constants used here.
+
+use gpui::{Hsla, hsla};
+
+const MAIN_TRC: f32 = 2.4;
+const RED_COEFFICIENT: f32 = 0.2126729;
+const GREEN_COEFFICIENT: f32 = 0.7151522;
+const BLUE_COEFFICIENT: f32 = 0.0721750;
+
+const NORMAL_BACKGROUND_EXPONENT: f32 = 0.56;
+const NORMAL_TEXT_EXPONENT: f32 = 0.57;
+const REVERSE_TEXT_EXPONENT: f32 = 0.62;
+const REVERSE_BACKGROUND_EXPONENT: f32 = 0.65;
+
+const BLACK_THRESHOLD: f32 = 0.022;
+const BLACK_CLAMP: f32 = 1.414;
+const SCALE: f32 = 1.14;
+const LOW_OFFSET: f32 = 0.027;
+const LOW_CLIP: f32 = 0.1;
+const DELTA_Y_MIN: f32 = 0.0005;
+
+/// How many halvings the lightness search makes, which is far finer than 8-bit color.
+const SEARCH_STEPS: usize = 16;
+
+/// Returns the APCA lightness contrast (Lc) of `text` on `background`, from about -108 to 106.
+///
+/// Positive values are dark text on a light background, negative ones light text on a dark one.
+pub fn apca_contrast(text: Hsla, background: Hsla) -> f32 {
+    // Translucent text is seen blended with what is behind it.
+    let text_y = soft_clamp_black(luminance(background.blend(text)));
+    let background_y = soft_clamp_black(luminance(background));
+    if (background_y - text_y).abs() < DELTA_Y_MIN {
+        return 0.;
+    }
+
+    let contrast = if background_y > text_y {
+        let contrast = (background_y.powf(NORMAL_BACKGROUND_EXPONENT)
+            - text_y.powf(NORMAL_TEXT_EXPONENT))
+            * SCALE;
+        if contrast < LOW_CLIP {
+            0.
+        } else {
+            contrast - LOW_OFFSET
+        }
+    } else {
+        let contrast = (background_y.powf(REVERSE_BACKGROUND_EXPONENT)
+            - text_y.powf(REVERSE_TEXT_EXPONENT))
+            * SCALE;
+        if contrast > -LOW_CLIP {
+            0.
+        } else {
+            contrast + LOW_OFFSET
+        }
+    };
+    contrast * 100.
+}
+
+/// Returns `foreground` with its lightness nudged until it has at least `minimum_contrast` APCA
+/// contrast against `background`, keeping its hue and saturation.
+///
+/// Colors are moved as little as possible, away from the background's lightness first. When no
+/// lightness of the hue is readable enough, black or white is used instead.
+pub fn ensure_minimum_contrast(foreground: Hsla, background: Hsla, minimum_contrast: f32) -> Hsla {
+    if minimum_contrast <= 0. || apca_contrast(foreground, background).abs() >= minimum_contrast {
+        return foreground;
+    }
+
+    let lighter_first = luminance(background.blend(foreground)) >= luminance(background);
+    for lighter in [lighter_first, !lighter_first] {
+        if let Some(adjusted) = adjust_lightness(foreground, background, minimum_contrast, lighter)
+        {
+            return adjusted;
+        }
+    }
+
+    let black = hsla(0., 0., 0., foreground.a);
+    let white = hsla(0., 0., 1., foreground.a);
+    if apca_contrast(white, background).abs() >= apca_contrast(black, background).abs() {
+        white
+    } else {
+        black
+    }
+}
+
+/// Finds the lightness closest to `foreground`'s that reaches `minimum_contrast`, searching
+/// towards white or black.
+fn adjust_lightness(
+    foreground: Hsla,
+    background: Hsla,
+    minimum_contrast: f32,
+    lighter: bool,
+) -> Option<Hsla> {
+    let with_lightness = |l: f32| hsla(foreground.h, foreground.s, l, foreground.a);
+    let is_readable =
+        |l: f32| apca_contrast(with_lightness(l), background).abs() >= minimum_contrast;
+
+    let mut readable = if lighter { 1. } else { 0. };
+    if !is_readable(readable) {
+        return None;
+    }
+    let mut unreadable = foreground.l;
+    for _ in 0..SEARCH_STEPS {
+        let middle = (readable + unreadable) / 2.;
+        if is_readable(middle) {
+            readable = middle;
+        } else {
+            unreadable = middle;
+        }
+    }
+    Some(with_lightness(readable))
+}
+
+/// The screen luminance (Y) of a color, as APCA estimates it.
+fn luminance(color: Hsla) -> f32 {
+    let color = color.to_rgb();
+    RED_COEFFICIENT * color.r.powf(MAIN_TRC)
+        + GREEN_COEFFICIENT * color.g.powf(MAIN_TRC)
+        + BLUE_COEFFICIENT * color.b.powf(MAIN_TRC)
+}
+
+/// Raises near-black luminances, which are perceived as lighter than they measure.
+fn soft_clamp_black(y: f32) -> f32 {
+    if y > BLACK_THRESHOLD {
+        y
+    } else {
+        y + (BLACK_THRESHOLD - y).powf(BLACK_CLAMP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::{Rgba, black, white};
+
+    use super::*;
+
+    fn hex(color: &str) -> Hsla {
+        Rgba::try_from(color).unwrap().into()
+    }
+
+    #[test]
+    fn test_apca_contrast_extremes() {
+        assert!((apca_contrast(black(), white()) - 106.04).abs() < 0.5);
+        assert!((apca_contrast(white(), black()) + 107.88).abs() < 0.5);
+        assert_eq!(apca_contrast(hex("#777777"), hex("#777777")), 0.);
+    }
+
+    #[test]
+    fn test_ensure_minimum_contrast_keeps_readable_colors() {
+        let foreground = hex("#c0caf5");
+        let background = hex("#1a1b26");
+        assert_eq!(
+            ensure_minimum_contrast(foreground, background, 45.),
+            foreground
+        );
+        assert_eq!(
+            ensure_minimum_contrast(hex("#000080"), black(), 0.),
+            hex("#000080")
+        );
+    }
+
+    #[test]
+    fn test_ensure_minimum_contrast_adjusts_lightness() {
+        let dark_blue = hex("#000080");
+        let adjusted = ensure_minimum_contrast(dark_blue, black(), 45.);
+        assert!(apca_contrast(adjusted, black()).abs() >= 45.);
+        assert!(adjusted.l > dark_blue.l);
+        assert_eq!((adjusted.h, adjusted.s), (dark_blue.h, dark_blue.s));
+
+        let yellow = hex("#e5e510");
+        let adjusted = ensure_minimum_contrast(yellow, white(), 60.);
+        assert!(apca_contrast(adjusted, white()).abs() >= 60.);
+        assert!(adjusted.l < yellow.l);
+    }
+
+    #[test]
+    fn test_ensure_minimum_contrast_falls_back_to_black_or_white() {
+        let gray = hex("#777777");
+        let adjusted = ensure_minimum_contrast(gray, gray, 106.);
+        assert!(adjusted == black() || adjusted == white());
+    }
+}
@@ -0,0 +1,349 @@
+use std::cmp::Reverse;
+
+use gpui::{
+    Action, AppContext as _, Context, Entity, FocusHandle, Focusable, MouseButton, Render,
+    ScrollHandle, SharedString, Subscription, Window, actions, div, prelude::*,
+};
+use gpui_component::{
+    ActiveTheme, h_flex,
+    input::{Input, InputEvent, InputState},
+    kbd::Kbd,
+    v_flex,
+};
+
+pub const COMMAND_PALETTE_KEY_CONTEXT: &str = "AlacrtermCommandPalette";
+
+actions!(
+    terminal,
+    [
+        /// Toggles the command palette listing every available action.
+        ToggleCommandPalette,
+        /// Dismisses the command palette.
+        DismissCommandPalette,
+        /// Selects the next command in the command palette.
+        SelectNextCommand,
+        /// Selects the previous command in the command palette.
+        SelectPreviousCommand,
+    ]
+);
+
+struct Command {
+    name: SharedString,
+    action: Box<dyn Action>,
+}
+
+pub struct CommandPalette {
+    /// The terminal the commands are collected from and dispatched to.
+    terminal_focus: FocusHandle,
+    input: Entity<InputState>,
+    visible: bool,
+    commands: Vec<Command>,
+    /// Indices into `commands` of the ones matching the query, best match first.
+    matches: Vec<usize>,
+    selected: usize,
+    scroll_handle: ScrollHandle,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl CommandPalette {
+    pub fn new(terminal_focus: FocusHandle, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let input = cx.new(|cx| InputState::new(window, cx).placeholder("Run a command"));
+        let input_subscription = cx.subscribe_in(&input, window, {
+            move |this, _, event: &InputEvent, window, cx| match event {
+                InputEvent::Change => this.update_matches(cx),
+                InputEvent::PressEnter { .. } => this.confirm(window, cx),
+                _ => {}
+            }
+        });
+        let input_focus = input.read(cx).focus_handle(cx);
+        let blur_subscription = cx.on_focus_out(&input_focus, window, |this, _, _, cx| {
+            this.hide(cx);
+        });
+
+        Self {
+            terminal_focus,
+            input,
+            visible: false,
+            commands: Vec::new(),
+            matches: Vec::new(),
+            selected: 0,
+            scroll_handle: ScrollHandle::new(),
+            _subscriptions: vec![input_subscription, blur_subscription],
+        }
+    }
+
+    pub(crate) fn toggle(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.visible {
+            self.dismiss(window, cx);
+        } else {
+            self.show(window, cx);
+        }
+    }
+
+    /// Collects the actions available to the terminal, which has to be focused.
+    fn show(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.commands = window
+            .available_actions(cx)
+            .into_iter()
+            .filter(|action| !action.partial_eq(&ToggleCommandPalette))
+            .map(|action| Command {
+                name: humanize_action_name(action.name()).into(),
+                action,
+            })
+            .collect();
+        self.commands.sort_by(|a, b| a.name.cmp(&b.name));
+        self.commands.dedup_by(|a, b| a.name == b.name);
+
+        self.visible = true;
+        self.input
+            .update(cx, |input, cx| input.set_value("", window, cx));
+        window.focus(&self.input.read(cx).focus_handle(cx), cx);
+        self.update_matches(cx);
+    }
+
+    fn hide(&mut self, cx: &mut Context<Self>) {
+        if self.visible {
+            self.visible = false;
+            self.commands.clear();
+            self.matches.clear();
+            cx.notify();
+        }
+    }
+
+    fn dismiss(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.hide(cx);
+        window.focus(&self.terminal_focus, cx);
+    }
+
+    fn dismiss_action(
+        &mut self,
+        _: &DismissCommandPalette,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.visible {
+            cx.propagate();
+            return;
+        }
+        self.dismiss(window, cx);
+    }
+
+    fn select_next(&mut self, _: &SelectNextCommand, _: &mut Window, cx: &mut Context<Self>) {
+        if !self.matches.is_empty() {
+            self.select((self.selected + 1) % self.matches.len(), cx);
+        }
+    }
+
+    fn select_previous(
+        &mut self,
+        _: &SelectPreviousCommand,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.matches.is_empty() {
+            let previous = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(self.matches.len() - 1);
+            self.select(previous, cx);
+        }
+    }
+
+    fn select(&mut self, index: usize, cx: &mut Context<Self>) {
+        self.selected = index;
+        self.scroll_handle.scroll_to_item(index);
+        cx.notify();
+    }
+
+    fn update_matches(&mut self, cx: &mut Context<Self>) {
+        let query = self.input.read(cx).value().to_string();
+        let mut matches = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(index, command)| {
+                fuzzy_score(&query, &command.name).map(|score| (Reverse(score), index))
+            })
+            .collect::<Vec<_>>();
+        // Commands are sorted by name, so equally good matches stay in that order.
+        matches.sort();
+        self.matches = matches.into_iter().map(|(_, index)| index).collect();
+        self.select(0, cx);
+    }
+
+    /// Runs the selected command in the terminal.
+    fn confirm(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(command) = self
+            .matches
+            .get(self.selected)
+            .and_then(|&index| self.commands.get(index))
+        else {
+            return;
+        };
+        let action = command.action.boxed_clone();
+        self.dismiss(window, cx);
+        window.dispatch_action(action, cx);
+    }
+}
+
+impl Render for CommandPalette {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let root = div()
+            .on_action(cx.listener(Self::dismiss_action))
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_previous));
+
+        if !self.visible {
+            return root;
+        }
+
+        let colors = &cx.theme().colors;
+        let commands = self.matches.iter().enumerate().map(|(position, &index)| {
+            let command = &self.commands[index];
+            h_flex()
+                .id(position)
+                .w_full()
+                .justify_between()
+                .gap_4()
+                .px_2()
+                .py_1()
+                .rounded_md()
+                .when(position == self.selected, |this| this.bg(colors.accent))
+                .hover(|style| style.bg(colors.accent))
+                .child(command.name.clone())
+                .children(Kbd::binding_for_action_in(
+                    &*command.action,
+                    &self.terminal_focus,
+                    window,
+                ))
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(move |this, _, window, cx| {
+                        this.selected = position;
+                        this.confirm(window, cx);
+                    }),
+                )
+        });
+
+        root.absolute().top_2().left_0().right_0().child(
+            h_flex().w_full().justify_center().child(
+                v_flex()
+                    .key_context(COMMAND_PALETTE_KEY_CONTEXT)
+                    .w_96()
+                    .gap_1()
+                    .p_1()
+                    .rounded_md()
+                    .border_1()
+                    .border_color(colors.border)
+                    .bg(colors.popover)
+                    .text_color(colors.popover_foreground)
+                    .child(Input::new(&self.input))
+                    .child(
+                        v_flex()
+                            .id("command-palette-commands")
+                            .max_h_80()
+                            .overflow_y_scroll()
+                            .track_scroll(&self.scroll_handle)
+                            .text_sm()
+                            .children(commands)
+                            .when(self.matches.is_empty(), |this| {
+                                this.child(
+                                    div()
+                                        .px_2()
+                                        .py_1()
+                                        .text_color(colors.muted_foreground)
+                                        .child("No matching commands"),
+                                )
+                            }),
+                    ),
+            ),
+        )
+    }
+}
+
+/// Turns an action name such as `terminal::ToggleViMode` into `terminal: toggle vi mode`.
+fn humanize_action_name(name: &str) -> String {
+    let (namespace, name) = name.rsplit_once("::").unwrap_or(("", name));
+    let mut humanized = String::new();
+    if !namespace.is_empty() {
+        humanized.push_str(&namespace.replace("::", " "));
+        humanized.push_str(": ");
+    }
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if c == '_' {
+            humanized.push(' ');
+        } else {
+            // Start a word on lowercase to uppercase transitions, keeping acronyms together.
+            if c.is_uppercase() && previous.is_some_and(|p| p.is_lowercase() || p.is_numeric()) {
+                humanized.push(' ');
+            }
+            humanized.extend(c.to_lowercase());
+        }
+        previous = Some(c);
+    }
+    humanized
+}
+
+/// Scores `candidate` as a match for `query`, whose characters have to appear in it in order,
+/// ignoring case and whitespace. Characters starting a word or following the previous match score
+/// higher. Returns `None` when it does not match.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<usize> {
+    let mut score = 0;
+    let mut candidate_chars = candidate.chars().enumerate().peekable();
+    let mut previous_match = None;
+    let mut previous_char = None;
+    for query_char in query.chars().filter(|c| !c.is_whitespace()) {
+        loop {
+            let (position, c) = candidate_chars.next()?;
+            let is_word_start = previous_char.is_none_or(|p: char| !p.is_alphanumeric());
+            previous_char = Some(c);
+            if c.to_lowercase().eq(query_char.to_lowercase()) {
+                score += 1;
+                if is_word_start {
+                    score += 3;
+                }
+                if previous_match.is_some_and(|p| p + 1 == position) {
+                    score += 2;
+                }
+                previous_match = Some(position);
+                break;
+            }
+        }
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_humanize_action_name() {
+        assert_eq!(
+            humanize_action_name("terminal::ToggleViMode"),
+            "terminal: toggle vi mode"
+        );
+        assert_eq!(
+            humanize_action_name("alacrterm::ImportColorScheme"),
+            "alacrterm: import color scheme"
+        );
+        assert_eq!(humanize_action_name("ScrollToTop"), "scroll to top");
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "terminal: clear"), Some(0));
+        assert!(fuzzy_score("tvm", "terminal: toggle vi mode").is_some());
+        assert!(fuzzy_score("vi mode", "terminal: toggle vi mode").is_some());
+        assert!(fuzzy_score("VIM", "terminal: toggle vi mode").is_some());
+        assert_eq!(fuzzy_score("mode vi", "terminal: toggle vi mode"), None);
+
+        // Word starts and consecutive characters rank higher.
+        let query = "sel";
+        assert!(
+            fuzzy_score(query, "terminal: select all")
+                > fuzzy_score(query, "terminal: show character palette")
+        );
+    }
+}
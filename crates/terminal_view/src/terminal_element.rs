@@ -9,10 +9,10 @@ use gpui::{
 };
 use itertools::Itertools;
 use settings::{IntoGpui, Settings};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use terminal::{
-    Cell, Color, Content, CursorShape, IndexedCell, Modes, NamedColor, Point, Range, Terminal,
-    TerminalBounds, is_app_chosen_exact_color as terminal_is_app_chosen_exact_color,
+    Cell, Color, Content, CursorShape, IndexedCell, Modes, NamedColor, Point, Range, ShellRegion,
+    Terminal, TerminalBounds, is_app_chosen_exact_color as terminal_is_app_chosen_exact_color,
     is_default_background_color, terminal_settings::TerminalSettings,
     ThemeColors
 };
@@ -23,7 +23,8 @@ use std::mem;
 use std::{fmt::Debug, rc::Rc};
 
 use crate::{
-    BlockContext, BlockProperties, ContentMode, TerminalMode, TerminalView, tooltip::Tooltip,
+    BlockContext, BlockProperties, ContentMode, TerminalMode, TerminalView,
+    color_contrast::ensure_minimum_contrast, tooltip::Tooltip,
 };
 
 const MIN_FONT_SIZE: Pixels = px(6.0);
@@ -106,8 +107,41 @@ fn adjusted_font_size(size: Pixels, cx: &App) -> Pixels {
     clamp_font_size(adjusted_font_size)
 }
 
-fn ensure_minimum_contrast(fg: Hsla, _bg: Hsla, _minimum_contrast: f32) -> Hsla {
-    fg
+/// The font size of standalone terminals before their per-pane zoom.
+pub(crate) fn terminal_font_size(cx: &App) -> Pixels {
+    match TerminalSettings::get_global(cx).font_size {
+        Some(size) => adjusted_font_size(size, cx),
+        None => ThemeFontSettings::get_global(cx).buffer_font_size(cx),
+    }
+}
+
+/// Zooms every terminal by `delta`, on top of the configured font size.
+pub(crate) fn adjust_global_font_size(delta: Pixels, cx: &mut App) {
+    // Terminals derive their size from the buffer font size, so bound the zoom by the terminal
+    // size, which can be far from the buffer one.
+    let size = terminal_font_size(cx);
+    let delta = clamp_font_size(size + delta) - size;
+    let buffer_font_size = cx
+        .try_global::<BufferFontSize>()
+        .map_or(ThemeFontSettings::get_global(cx).buffer_font_size, |size| {
+            size.0
+        });
+    cx.set_global(BufferFontSize(buffer_font_size + delta));
+    cx.refresh_windows();
+}
+
+/// Returns every terminal to the configured font size.
+pub(crate) fn reset_global_font_size(cx: &mut App) {
+    if cx.has_global::<BufferFontSize>() {
+        cx.remove_global::<BufferFontSize>();
+        cx.refresh_windows();
+    }
+}
+
+/// Limits a per-pane zoom so the zoomed font size stays within bounds.
+pub(crate) fn clamp_font_size_adjustment(adjustment: Pixels, cx: &App) -> Pixels {
+    let base_size = terminal_font_size(cx);
+    clamp_font_size(base_size + adjustment) - base_size
 }
 
 #[derive(Clone, Copy)]
@@ -205,6 +239,7 @@ pub struct LayoutState {
     mode: Modes,
     display_offset: usize,
     hyperlink_tooltip: Option<AnyElement>,
+    prompt_markers: Vec<AnyElement>,
     block_below_cursor_element: Option<AnyElement>,
     base_text_style: TextStyle,
     content_mode: ContentMode,
@@ -529,6 +564,85 @@ impl TerminalElement {
         .track_focus(&focus)
     }
 
+    /// Lays out a marker in the gutter next to each prompt whose command was run, colored by the
+    /// command's exit status and showing how long it ran on hover. Once the command finished, its
+    /// duration is also shown at the end of the prompt line. `prompts` holds the display line and
+    /// the command block of each prompt, `origin` is the top of the gutter and `width` the width
+    /// of the gutter and the lines together.
+    #[allow(clippy::too_many_arguments)]
+    fn layout_prompt_markers(
+        &self,
+        prompts: &[(i32, u64)],
+        origin: GpuiPoint<Pixels>,
+        gutter: Pixels,
+        width: Pixels,
+        line_height: Pixels,
+        colors: &ThemeColors,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Vec<AnyElement> {
+        let mut markers = Vec::with_capacity(prompts.len());
+        for &(line, block) in prompts {
+            let Some(command) = self.terminal.read(cx).command_block(block) else {
+                continue;
+            };
+            let Some(duration) = command.duration().map(format_duration) else {
+                continue;
+            };
+            let (color, status) = match (command.finished_at, command.exit_code) {
+                (None, _) => (
+                    colors.terminal_ansi_bright_black,
+                    format!("Running for {duration}"),
+                ),
+                (Some(_), Some(0)) => (
+                    colors.terminal_ansi_green,
+                    format!("Succeeded in {duration}"),
+                ),
+                (Some(_), Some(code)) => (
+                    colors.terminal_ansi_red,
+                    format!("Failed with exit code {code} in {duration}"),
+                ),
+                (Some(_), None) => (
+                    colors.terminal_ansi_bright_black,
+                    format!("Finished in {duration}"),
+                ),
+            };
+
+            let origin = origin + point(px(0.), line as f32 * line_height);
+            if command.finished_at.is_some() {
+                let mut label = div()
+                    .size_full()
+                    .flex()
+                    .justify_end()
+                    .items_center()
+                    .child(
+                        div()
+                            .px_1()
+                            .rounded_sm()
+                            .bg(colors.terminal_background)
+                            .text_color(color)
+                            .text_size(line_height * 0.75)
+                            .child(duration),
+                    )
+                    .into_any_element();
+                label.prepaint_as_root(origin, size(width, line_height).into(), window, cx);
+                markers.push(label);
+            }
+
+            let mut element = div()
+                .id(("prompt-marker", block as usize))
+                .size_full()
+                .flex()
+                .justify_center()
+                .tooltip(Tooltip::text(status))
+                .child(div().w(gutter / 3.).h_full().rounded_sm().bg(color))
+                .into_any_element();
+            element.prepaint_as_root(origin, size(gutter, line_height).into(), window, cx);
+            markers.push(element);
+        }
+        markers
+    }
+
     pub fn layout_grid<T: TerminalLayoutCell>(
         grid: impl Iterator<Item = T>,
         start_line_offset: i32,
@@ -1108,9 +1222,10 @@ impl Element for TerminalElement {
                     TerminalMode::Embedded { .. } => {
                         window.text_style().font_size.to_pixels(window.rem_size())
                     }
-                    TerminalMode::Standalone => terminal_settings.font_size.map_or_else(
-                        || theme_font_settings.buffer_font_size(cx),
-                        |size| adjusted_font_size(size, cx),
+                    // Size changes reach the PTY through `set_size` below, as the cell size
+                    // changes with them.
+                    TerminalMode::Standalone => clamp_font_size(
+                        terminal_font_size(cx) + self.terminal_view.read(cx).font_size_adjustment,
                     ),
                 };
 
@@ -1270,6 +1385,14 @@ impl Element for TerminalElement {
                 } = &self.terminal.read(cx).last_content;
                 let mode = *mode;
                 let display_offset = *display_offset;
+                let prompts = cells
+                    .iter()
+                    .filter_map(|cell| {
+                        let mark = cell.shell_mark()?;
+                        (mark.region == ShellRegion::Prompt)
+                            .then_some((cell.point.line + display_offset as i32, mark.block))
+                    })
+                    .collect::<Vec<_>>();
 
                 // searches, highlights to a single range representations
                 let mut relative_highlighted_ranges = Vec::new();
@@ -1446,6 +1569,17 @@ impl Element for TerminalElement {
                     None
                 };
 
+                let prompt_markers = self.layout_prompt_markers(
+                    &prompts,
+                    point(bounds.origin.x, dimensions.bounds.origin.y - scroll_top),
+                    gutter,
+                    dimensions.width() + gutter,
+                    dimensions.line_height(),
+                    &colors,
+                    window,
+                    cx,
+                );
+
                 LayoutState {
                     hitbox,
                     batched_text_runs,
@@ -1458,6 +1592,7 @@ impl Element for TerminalElement {
                     mode,
                     display_offset,
                     hyperlink_tooltip,
+                    prompt_markers,
                     block_below_cursor_element,
                     base_text_style: text_style,
                     content_mode,
@@ -1516,6 +1651,7 @@ impl Element for TerminalElement {
 
             let original_cursor = layout.cursor.take();
             let hyperlink_tooltip = layout.hyperlink_tooltip.take();
+            let prompt_markers = mem::take(&mut layout.prompt_markers);
             let block_below_cursor_element = layout.block_below_cursor_element.take();
             self.interactivity.paint(
                 global_id,
@@ -1529,12 +1665,18 @@ impl Element for TerminalElement {
 
                     window.on_key_event({
                         let this = self.terminal.clone();
+                        let focus = self.focus.clone();
                         move |event: &ModifiersChangedEvent, phase, window, cx| {
                             if phase != DispatchPhase::Bubble {
                                 return;
                             }
 
                             this.update(cx, |term, cx| {
+                                // Every visible terminal sees the event, only the focused one
+                                // gets the keys.
+                                if focus.is_focused(window) {
+                                    term.report_modifiers_change(&event.modifiers);
+                                }
                                 term.try_modifiers_change(&event.modifiers, window, cx)
                             });
                         }
@@ -1618,6 +1760,10 @@ impl Element for TerminalElement {
                         cursor.paint(origin, window, cx);
                     }
 
+                    for mut marker in prompt_markers {
+                        marker.paint(window, cx);
+                    }
+
                     if let Some(mut element) = block_below_cursor_element {
                         element.paint(window, cx);
                     }
@@ -1880,6 +2026,38 @@ pub fn convert_color(fg: &Color, colors: &ThemeColors) -> Hsla {
         // 'True' colors
         Color::Spec(rgb) => terminal::rgba_color(rgb.r, rgb.g, rgb.b),
         // 8 bit, indexed colors
-        Color::Indexed(i) => terminal::get_color_at_index(*i as usize, &ThemeColors::dark()),
+        Color::Indexed(i) => terminal::get_color_at_index(*i as usize, colors),
+    }
+}
+
+/// Formats how long a command ran, such as `850ms`, `4.2s` or `3m 12s`.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds < 1 {
+        format!("{}ms", duration.as_millis())
+    } else if seconds < 60 {
+        format!("{:.1}s", duration.as_secs_f32())
+    } else if seconds < 60 * 60 {
+        format!("{}m {}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}h {}m", seconds / 3600, seconds / 60 % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(0)), "0ms");
+        assert_eq!(format_duration(Duration::from_millis(850)), "850ms");
+        assert_eq!(format_duration(Duration::from_millis(4230)), "4.2s");
+        assert_eq!(format_duration(Duration::from_secs(59)), "59.0s");
+        assert_eq!(format_duration(Duration::from_secs(192)), "3m 12s");
+        assert_eq!(
+            format_duration(Duration::from_secs(2 * 3600 + 5 * 60 + 9)),
+            "2h 5m"
+        );
     }
 }
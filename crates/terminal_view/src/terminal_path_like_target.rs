@@ -13,6 +13,10 @@ pub(super) fn hover_path_like_target(
         .map(|terminal_dir| terminal_dir.join(&path_like_target.maybe_path))
         .map(|path| path.to_string_lossy().into_owned())
         .unwrap_or_else(|| path_like_target.maybe_path.clone());
+    let tooltip = match &path_like_target.terminal_host {
+        Some(host) => format!("{host}:{tooltip}"),
+        None => tooltip,
+    };
 
     cx.spawn(async move |terminal_view, cx| {
         terminal_view
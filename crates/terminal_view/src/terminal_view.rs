@@ -1,4 +1,6 @@
 mod blink_manager;
+mod color_contrast;
+pub mod command_palette;
 mod context_menu;
 pub mod scrollbar;
 pub mod search_bar;
@@ -7,14 +9,15 @@ mod terminal_path_like_target;
 pub mod terminal_scrollbar;
 pub mod tooltip;
 
+use anyhow::Context as _;
 use gpui::{
-    Action, AnyElement, App, AppContext as _, ClipboardEntry, Context, DismissEvent, Entity,
-    EventEmitter, FocusHandle, Focusable, IntoElement, KeyContext,
-    KeyDownEvent, Keystroke, MouseButton, MouseDownEvent, Pixels, Point as GpuiPoint, Render,
+    Action, AnyElement, App, AppContext as _, ClipboardEntry, ClipboardItem, Context, DismissEvent,
+    Entity, EventEmitter, FocusHandle, Focusable, IntoElement, KeyContext, KeyDownEvent,
+    KeyUpEvent, Keystroke, MouseButton, MouseDownEvent, Pixels, Point as GpuiPoint, Render,
     ScrollWheelEvent, Styled, Subscription, Task, Window, actions, anchored, deferred, div,
     prelude::*, px,
 };
-use gpui_component::v_flex;
+use gpui_component::{ActiveTheme, h_flex, v_flex};
 use schemars::JsonSchema;
 use serde::Deserialize;
 use settings::{
@@ -29,21 +32,29 @@ use std::{
     time::Duration,
 };
 use terminal::{
-    ActiveColors, Clear, Copy, Event, HoveredWord, MaybeNavigationTarget, Modes, Paste, PasteText, Point, Range, ScrollLineDown, ScrollLineUp, ScrollPageDown, ScrollPageUp, ScrollToBottom, ScrollToTop, Search, SelectAll, ShowCharacterPalette, Terminal, TerminalBounds, ThemeColors, ToggleViMode, terminal_settings::{CursorShape, TerminalSettings}
+    ActiveColors, Clear, Copy, CopyLastCommandOutput, Event, HoveredWord, JumpToNextPrompt,
+    JumpToPreviousPrompt, MaybeNavigationTarget, Modes, Paste, PasteText, Point, Range,
+    SaveLastCommandOutput, ScrollLineDown, ScrollLineUp, ScrollPageDown, ScrollPageUp,
+    ScrollToBottom, ScrollToTop, Search, SelectAll, SelectCommandOutput, ShowCharacterPalette,
+    Terminal, TerminalBounds, ThemeColors, ToggleViMode,
+    terminal_settings::{CursorShape, TerminalSettings},
+};
+use terminal_element::{
+    TerminalElement, adjust_global_font_size, clamp_font_size_adjustment, reset_global_font_size,
 };
-use terminal_element::TerminalElement;
 use terminal_path_like_target::{hover_path_like_target, open_path_like_target};
 use terminal_scrollbar::TerminalScrollHandle;
 use util::ResultExt;
 
 use crate::blink_manager::BlinkManager;
+use crate::command_palette::{CommandPalette, ToggleCommandPalette};
 use crate::context_menu::ContextMenu;
 use crate::scrollbar::scrollbars::{ScrollbarVisibility, ShowScrollbar};
 use crate::scrollbar::{ScrollAxes, Scrollbars, WithScrollbar};
 use crate::search_bar::{
-    DismissTerminalSearch, SEARCH_BAR_KEY_CONTEXT, SelectNextSearchMatch,
-    SelectPreviousSearchMatch, TerminalSearchBar, TerminalSearchDirection, TerminalSearchOptions,
-    TerminalSearchQuery, TerminalSearchable, ToggleTerminalSearch, ToggleTerminalSearchRegex,
+    DismissTerminalSearch, SelectNextSearchMatch, SelectPreviousSearchMatch, TerminalSearchBar,
+    TerminalSearchDirection, TerminalSearchOptions, TerminalSearchQuery, TerminalSearchable,
+    ToggleTerminalSearch, ToggleTerminalSearchRegex,
 };
 struct ImeState {
     marked_text: String,
@@ -60,6 +71,7 @@ fn viewport_line_for_point(point: Point, display_offset: usize) -> Option<usize>
 }
 
 const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
+const FONT_SIZE_STEP: Pixels = px(1.0);
 
 /// 将滚动从元素传输到视图的事件
 #[derive(Clone, Debug, PartialEq)]
@@ -80,6 +92,8 @@ actions!(
     [
         /// Reruns the last executed task in the terminal.
         RerunTask,
+        /// Restarts a terminal whose process has exited.
+        RestartTerminal,
     ]
 );
 
@@ -88,23 +102,36 @@ actions!(
 #[action(namespace = terminal)]
 pub struct RenameTerminal;
 
+/// Increases the font size of the terminal, or of every terminal with `global`.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Action)]
+#[action(namespace = terminal)]
+#[serde(deny_unknown_fields)]
+pub struct IncreaseFontSize {
+    #[serde(default)]
+    pub global: bool,
+}
+
+/// Decreases the font size of the terminal, or of every terminal with `global`.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Action)]
+#[action(namespace = terminal)]
+#[serde(deny_unknown_fields)]
+pub struct DecreaseFontSize {
+    #[serde(default)]
+    pub global: bool,
+}
+
+/// Resets the font size of the terminal, or of every terminal with `global`, to the configured
+/// one.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Action)]
+#[action(namespace = terminal)]
+#[serde(deny_unknown_fields)]
+pub struct ResetFontSize {
+    #[serde(default)]
+    pub global: bool,
+}
+
 pub fn init(cx: &mut App) {
     cx.set_global(ThemeColors::dark());
-    cx.bind_keys([
-        gpui::KeyBinding::new("ctrl-f", ToggleTerminalSearch, None),
-        gpui::KeyBinding::new(
-            "escape",
-            DismissTerminalSearch,
-            Some(SEARCH_BAR_KEY_CONTEXT),
-        ),
-        gpui::KeyBinding::new("f3", SelectNextSearchMatch, None),
-        gpui::KeyBinding::new("shift-f3", SelectPreviousSearchMatch, None),
-        gpui::KeyBinding::new(
-            "ctrl-r",
-            ToggleTerminalSearchRegex,
-            Some(SEARCH_BAR_KEY_CONTEXT),
-        ),
-    ]);
 }
 
 pub struct BlockProperties {
@@ -131,6 +158,10 @@ pub struct TerminalView {
     blink_manager: Entity<BlinkManager>,
     mode: TerminalMode,
     blinking_terminal_enabled: bool,
+    /// Set when the process has exited and the view keeps showing its final screen.
+    exited: bool,
+    /// The last working directory of the process, where it is restarted once it has exited.
+    last_working_directory: Option<PathBuf>,
     // needs_serialize: bool,
     custom_title: Option<String>,
     hover: Option<HoverTarget>,
@@ -142,6 +173,11 @@ pub struct TerminalView {
     scroll_handle: TerminalScrollHandle,
     ime_state: Option<ImeState>,
     search_bar: Entity<TerminalSearchBar>,
+    /// The zoom of this pane, added to the font size of every terminal.
+    font_size_adjustment: Pixels,
+    /// Ctrl+scroll-wheel movement not yet turned into zoom steps.
+    pending_zoom_scroll: Pixels,
+    command_palette: Entity<CommandPalette>,
     // self_handle: WeakEntity<Self>,
     // rename_editor: Option<Entity<Editor>>,
     // rename_editor_subscription: Option<Subscription>,
@@ -232,6 +268,7 @@ impl TerminalView {
 
         let terminal_view = cx.entity().downgrade();
         let search_bar = cx.new(|cx| TerminalSearchBar::new(terminal_view, window, cx));
+        let command_palette = cx.new(|cx| CommandPalette::new(focus_handle.clone(), window, cx));
 
         let subscriptions = vec![
             focus_in,
@@ -249,6 +286,8 @@ impl TerminalView {
             blink_manager,
             mode: TerminalMode::Standalone,
             blinking_terminal_enabled: false,
+            exited: false,
+            last_working_directory: None,
             hover: None,
             hover_tooltip_update: Task::ready(()),
             block_below_cursor: None,
@@ -257,6 +296,9 @@ impl TerminalView {
             custom_title: None,
             ime_state: None,
             search_bar,
+            font_size_adjustment: Pixels::ZERO,
+            pending_zoom_scroll: Pixels::ZERO,
+            command_palette,
             _subscriptions: subscriptions,
             _terminal_subscriptions: subscribe_for_terminal_events(&terminal, window, cx),
         }
@@ -356,6 +398,18 @@ impl TerminalView {
         self.custom_title.as_deref()
     }
 
+    /// The window title while this terminal is active, following the `title` setting unless the
+    /// tab was renamed.
+    pub fn window_title(&self, cx: &App) -> String {
+        match self.custom_title() {
+            Some(custom_title) => custom_title.to_string(),
+            None => self
+                .terminal
+                .read(cx)
+                .title_from_template(&TerminalSettings::get_global(cx).title),
+        }
+    }
+
     pub fn set_custom_title(&mut self, label: Option<String>, cx: &mut Context<Self>) {
         let label = label.filter(|l| !l.trim().is_empty());
         if self.custom_title != label {
@@ -386,6 +440,149 @@ impl TerminalView {
         let _ = cx;
     }
 
+    fn increase_font_size(
+        &mut self,
+        action: &IncreaseFontSize,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.adjust_font_size(FONT_SIZE_STEP, action.global, cx);
+    }
+
+    fn decrease_font_size(
+        &mut self,
+        action: &DecreaseFontSize,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.adjust_font_size(-FONT_SIZE_STEP, action.global, cx);
+    }
+
+    fn reset_font_size(&mut self, action: &ResetFontSize, _: &mut Window, cx: &mut Context<Self>) {
+        if action.global {
+            reset_global_font_size(cx);
+        } else {
+            self.font_size_adjustment = Pixels::ZERO;
+            cx.notify();
+        }
+    }
+
+    fn adjust_font_size(&mut self, delta: Pixels, global: bool, cx: &mut Context<Self>) {
+        if global {
+            adjust_global_font_size(delta, cx);
+        } else {
+            self.font_size_adjustment =
+                clamp_font_size_adjustment(self.font_size_adjustment + delta, cx);
+            cx.notify();
+        }
+    }
+
+    pub fn is_exited(&self) -> bool {
+        self.exited
+    }
+
+    /// Keeps showing the final screen of a terminal whose process has exited, along with its
+    /// exit status and a way to restart it.
+    pub fn hold(&mut self, cx: &mut Context<Self>) {
+        if !self.exited {
+            self.exited = true;
+            cx.notify();
+        }
+    }
+
+    fn restart(&mut self, _: &RestartTerminal, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.exited {
+            cx.propagate();
+            return;
+        }
+
+        let builder = self
+            .terminal
+            .read(cx)
+            .clone_builder(cx, self.last_working_directory.clone());
+        cx.spawn_in(window, async move |this, cx| {
+            let builder = builder.await?;
+            this.update_in(cx, |this, window, cx| {
+                let terminal = cx.new(|cx| builder.subscribe(cx));
+                this.set_terminal(terminal, window, cx);
+                this.exited = false;
+                cx.emit(Event::TitleChanged);
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// The toolbar showing the title set by the program running in the terminal, when enabled.
+    fn render_toolbar(&self, cx: &App) -> Option<impl IntoElement> {
+        if !TerminalSettings::get_global(cx).toolbar.breadcrumbs {
+            return None;
+        }
+        let breadcrumb_text = self.terminal.read(cx).breadcrumb_text.trim();
+        if breadcrumb_text.is_empty() {
+            return None;
+        }
+
+        let colors = &cx.theme().colors;
+        Some(
+            h_flex()
+                .w_full()
+                .px_2()
+                .py_0p5()
+                .border_b_1()
+                .border_color(colors.border)
+                .bg(colors.background)
+                .text_sm()
+                .text_color(colors.muted_foreground)
+                .child(
+                    div()
+                        .min_w_0()
+                        .overflow_hidden()
+                        .text_ellipsis()
+                        .whitespace_nowrap()
+                        .child(breadcrumb_text.to_string()),
+                ),
+        )
+    }
+
+    fn render_exit_overlay(&self, cx: &App) -> impl IntoElement {
+        let message = match self.terminal.read(cx).child_exited() {
+            Some(status) => match status.code() {
+                Some(code) => format!("Process exited with code {code}"),
+                None => format!("Process exited ({status})"),
+            },
+            None => "Process exited".to_string(),
+        };
+
+        let colors = &cx.theme().colors;
+        h_flex()
+            .absolute()
+            .bottom_2()
+            .right_2()
+            .gap_2()
+            .items_center()
+            .px_2()
+            .py_1()
+            .rounded_md()
+            .border_1()
+            .border_color(colors.border)
+            .bg(colors.popover)
+            .text_sm()
+            .child(message)
+            .child(
+                div()
+                    .id("restart-terminal")
+                    .px_2()
+                    .rounded_md()
+                    .border_1()
+                    .border_color(colors.border)
+                    .child("Restart")
+                    .on_mouse_down(MouseButton::Left, |_, window, cx| {
+                        window.dispatch_action(Box::new(RestartTerminal), cx);
+                    }),
+            )
+    }
+
     pub fn clear_bell(&mut self, cx: &mut Context<TerminalView>) {
         self.has_bell = false;
         cx.emit(Event::Wakeup);
@@ -398,12 +595,26 @@ impl TerminalView {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        // By the time an entry is picked, the mouse is over the menu rather than the command.
+        let terminal = self.terminal.downgrade();
         let context_menu = ContextMenu::build(window, cx, |menu, _, _| {
             menu.context(self.focus_handle.clone())
                 .action("Copy", Box::new(Copy))
                 .action("Paste", Box::new(Paste))
                 .action("Paste Text", Box::new(PasteText))
                 .action("Select All", Box::new(SelectAll))
+                .entry(
+                    "Select Command Output",
+                    Some(Box::new(SelectCommandOutput)),
+                    move |_, cx| {
+                        terminal
+                            .update(cx, |term, cx| {
+                                term.select_command_output(position);
+                                cx.notify();
+                            })
+                            .ok();
+                    },
+                )
                 .action("Clear", Box::new(Clear))
         });
         let _ = has_selection;
@@ -441,6 +652,13 @@ impl TerminalView {
                 term.set_cursor_shape(self.cursor_shape);
             });
         }
+        self.terminal.update(cx, |term, _| {
+            term.set_max_scroll_history_lines(settings.max_scroll_history_lines);
+            term.set_path_hyperlink_regexes(
+                settings.path_hyperlink_regexes.clone(),
+                settings.path_hyperlink_timeout_ms,
+            );
+        });
 
         self.blink_manager.update(
             cx,
@@ -483,6 +701,54 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn select_command_output(
+        &mut self,
+        _: &SelectCommandOutput,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.terminal.update(cx, |term, _| {
+            term.select_command_output(window.mouse_position())
+        });
+        cx.notify();
+    }
+
+    fn copy_last_command_output(
+        &mut self,
+        _: &CopyLastCommandOutput,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(output) = self.terminal.read(cx).last_command_output() {
+            cx.write_to_clipboard(ClipboardItem::new_string(output));
+        }
+    }
+
+    /// Asks where to save the output of the last command, starting in the working directory.
+    fn save_last_command_output(
+        &mut self,
+        _: &SaveLastCommandOutput,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let terminal = self.terminal.read(cx);
+        let Some(output) = terminal.last_command_output() else {
+            return;
+        };
+        let directory = terminal
+            .local_working_directory()
+            .unwrap_or_else(|| util::paths::home_dir().clone());
+        let path = cx.prompt_for_new_path(&directory, Some("output.txt"));
+        cx.background_spawn(async move {
+            let Some(path) = path.await?? else {
+                return anyhow::Ok(());
+            };
+            std::fs::write(&path, output)
+                .with_context(|| format!("failed to save command output to {path:?}"))
+        })
+        .detach_and_log_err(cx);
+    }
+
     fn rerun_task(&mut self, _: &RerunTask, _window: &mut Window, cx: &mut Context<Self>) {
         let _ = cx;
     }
@@ -516,6 +782,17 @@ impl TerminalView {
     fn scroll_wheel(&mut self, event: &ScrollWheelEvent, cx: &mut Context<Self>) {
         let terminal_content = self.terminal.read(cx).last_content();
 
+        if event.modifiers.control {
+            let line_height = terminal_content.terminal_bounds.line_height;
+            self.pending_zoom_scroll += event.delta.pixel_delta(line_height).y;
+            while line_height > Pixels::ZERO && self.pending_zoom_scroll.abs() >= line_height {
+                let direction = self.pending_zoom_scroll.signum();
+                self.pending_zoom_scroll -= line_height * direction;
+                self.adjust_font_size(FONT_SIZE_STEP * direction, false, cx);
+            }
+            return;
+        }
+
         if self.block_below_cursor.is_some() && terminal_content.display_offset == 0 {
             let line_height = terminal_content.terminal_bounds.line_height;
             let y_delta = event.delta.pixel_delta(line_height).y;
@@ -651,6 +928,38 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn jump_to_previous_prompt(
+        &mut self,
+        _: &JumpToPreviousPrompt,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.is_alt_screen(cx) {
+            cx.propagate();
+            return;
+        }
+
+        self.terminal
+            .update(cx, |term, _| term.jump_to_previous_prompt());
+        cx.notify();
+    }
+
+    fn jump_to_next_prompt(
+        &mut self,
+        _: &JumpToNextPrompt,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.is_alt_screen(cx) {
+            cx.propagate();
+            return;
+        }
+
+        self.terminal
+            .update(cx, |term, _| term.jump_to_next_prompt());
+        cx.notify();
+    }
+
     fn toggle_vi_mode(&mut self, _: &ToggleViMode, _: &mut Window, cx: &mut Context<Self>) {
         self.terminal.update(cx, |term, _| term.toggle_vi_mode());
         cx.notify();
@@ -809,7 +1118,7 @@ impl TerminalView {
         if let Some(keystroke) = Keystroke::parse(&text.0).log_err() {
             self.clear_bell(cx);
             self.blink_manager.update(cx, BlinkManager::pause_blinking);
-            self.process_keystroke(&keystroke, cx);
+            self.process_keystroke(&keystroke, false, cx);
         }
     }
 
@@ -894,6 +1203,10 @@ impl TerminalView {
             dispatch_context.add("selection");
         }
 
+        if self.exited {
+            dispatch_context.add("exited");
+        }
+
         dispatch_context
     }
 
@@ -953,6 +1266,10 @@ fn subscribe_for_terminal_events(
             if current_cwd != previous_cwd {
                 previous_cwd = current_cwd;
             }
+            // The process' directory can't be read anymore once it has exited.
+            if let Some(cwd) = terminal.read(cx).local_working_directory() {
+                terminal_view.last_working_directory = Some(cwd);
+            }
 
             match event {
                 Event::Wakeup => {
@@ -989,6 +1306,7 @@ fn subscribe_for_terminal_events(
 
                 Event::TitleChanged => {
                     cx.notify();
+                    cx.emit(Event::TitleChanged);
                 }
 
                 Event::NewNavigationTarget(maybe_navigation_target) => {
@@ -1041,8 +1359,14 @@ fn subscribe_for_terminal_events(
                         open_path_like_target(path_like_target, window, cx)
                     }
                 },
-                Event::BreadcrumbsChanged => cx.notify(),
-                Event::CloseTerminal => cx.notify(),
+                Event::BreadcrumbsChanged => {
+                    cx.notify();
+                    cx.emit(Event::BreadcrumbsChanged);
+                }
+                Event::CloseTerminal => {
+                    cx.notify();
+                    cx.emit(Event::CloseTerminal);
+                }
                 Event::SelectionsChanged => {
                     window.invalidate_character_coordinates();
                     cx.notify();
@@ -1085,12 +1409,20 @@ impl TerminalView {
     /// In vi mode, explicitly triggers a re-render because vi navigation (like j/k)
     /// updates the cursor locally without sending data to the shell, so there's no
     /// shell output to automatically trigger a re-render.
-    fn process_keystroke(&mut self, keystroke: &Keystroke, cx: &mut Context<Self>) -> bool {
+    fn process_keystroke(
+        &mut self,
+        keystroke: &Keystroke,
+        is_held: bool,
+        cx: &mut Context<Self>,
+    ) -> bool {
         let (handled, vi_mode_enabled) = self.terminal.update(cx, |term, cx| {
-            (
-                term.try_keystroke(keystroke, TerminalSettings::get_global(cx).option_as_meta),
-                term.vi_mode_enabled(),
-            )
+            let option_as_meta = TerminalSettings::get_global(cx).option_as_meta;
+            let handled = if is_held {
+                term.try_key_repeat(keystroke, option_as_meta)
+            } else {
+                term.try_keystroke(keystroke, option_as_meta)
+            };
+            (handled, term.vi_mode_enabled())
         });
 
         if handled && vi_mode_enabled {
@@ -1101,10 +1433,31 @@ impl TerminalView {
     }
 
     fn key_down(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        // Keys typed into the search bar or command palette bubble up here too.
+        if !self.focus_handle.is_focused(window) {
+            return;
+        }
+
         self.clear_bell(cx);
         self.pause_cursor_blinking(window, cx);
 
-        if self.process_keystroke(&event.keystroke, cx) {
+        if self.process_keystroke(&event.keystroke, event.is_held, cx) {
+            cx.stop_propagation();
+        }
+    }
+
+    fn key_up(&mut self, event: &KeyUpEvent, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.focus_handle.is_focused(window) {
+            return;
+        }
+
+        let handled = self.terminal.update(cx, |term, cx| {
+            term.try_key_release(
+                &event.keystroke,
+                TerminalSettings::get_global(cx).option_as_meta,
+            )
+        });
+        if handled {
             cx.stop_propagation();
         }
     }
@@ -1244,6 +1597,10 @@ impl Render for TerminalView {
                         .detach();
                 }),
             )
+            .on_action(cx.listener(|this, _: &ToggleCommandPalette, window, cx| {
+                this.command_palette
+                    .update(cx, |command_palette, cx| command_palette.toggle(window, cx));
+            }))
             .on_action(cx.listener(TerminalView::send_text))
             .on_action(cx.listener(TerminalView::send_keystroke))
             .on_action(cx.listener(TerminalView::copy))
@@ -1256,11 +1613,21 @@ impl Render for TerminalView {
             .on_action(cx.listener(TerminalView::scroll_page_down))
             .on_action(cx.listener(TerminalView::scroll_to_top))
             .on_action(cx.listener(TerminalView::scroll_to_bottom))
+            .on_action(cx.listener(TerminalView::jump_to_previous_prompt))
+            .on_action(cx.listener(TerminalView::jump_to_next_prompt))
             .on_action(cx.listener(TerminalView::toggle_vi_mode))
             .on_action(cx.listener(TerminalView::show_character_palette))
             .on_action(cx.listener(TerminalView::select_all))
+            .on_action(cx.listener(TerminalView::select_command_output))
+            .on_action(cx.listener(TerminalView::copy_last_command_output))
+            .on_action(cx.listener(TerminalView::save_last_command_output))
             .on_action(cx.listener(TerminalView::rename_terminal))
+            .on_action(cx.listener(TerminalView::increase_font_size))
+            .on_action(cx.listener(TerminalView::decrease_font_size))
+            .on_action(cx.listener(TerminalView::reset_font_size))
+            .on_action(cx.listener(TerminalView::restart))
             .on_key_down(cx.listener(Self::key_down))
+            .on_key_up(cx.listener(Self::key_up))
             .on_mouse_down(
                 MouseButton::Right,
                 cx.listener(|this, event: &MouseDownEvent, window, cx| {
@@ -1284,6 +1651,7 @@ impl Render for TerminalView {
                     }
                 }),
             )
+            .children(self.render_toolbar(cx))
             .child(self.search_bar.clone())
             .child(
                 // TODO: Oddly this wrapper div is needed for TerminalElement to not steal events from the context menu
@@ -1316,6 +1684,8 @@ impl Render for TerminalView {
                         )
                     }),
             )
+            .when(self.exited, |div| div.child(self.render_exit_overlay(cx)))
+            .child(self.command_palette.clone())
             .children(self.context_menu.as_ref().map(|(menu, position, _)| {
                 deferred(
                     anchored()
@@ -1762,7 +2132,7 @@ impl SerializableItem for TerminalView {
         }
 
         let workspace_id = self.workspace_id?;
-        let cwd = terminal.working_directory();
+        let cwd = terminal.local_working_directory();
         let custom_title = self.custom_title.clone();
         self.needs_serialize = false;
 
@@ -150,7 +150,10 @@ pub fn strip_json_comments(content: &str) -> String {
             }
 
             if matches!(ch, '}' | ']') {
-                pending_comma = None;
+                // Drop the trailing comma, but keep the line breaks after it so that
+                // parse errors still point at the right line.
+                let trailing = pending_comma.take().unwrap();
+                without_comments.push_str(&trailing[1..]);
                 without_comments.push(ch);
                 continue;
             }